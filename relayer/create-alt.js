@@ -43,10 +43,23 @@ async function main() {
     process.exit(1);
   }
 
-  // Derive PDAs (matching siphon-zk-pool constants.rs seeds)
-  const [merkleTreePDA] = PublicKey.findProgramAddressSync([Buffer.from('merkle_tree')], PROGRAM_ID);
-  const [poolConfigPDA] = PublicKey.findProgramAddressSync([Buffer.from('pool_config')], PROGRAM_ID);
-  const [poolVaultPDA] = PublicKey.findProgramAddressSync([Buffer.from('pool_vault')], PROGRAM_ID);
+  // Derive PDAs (matching siphon-zk-pool constants.rs seeds); all are keyed by the pool
+  const poolIdBuf = Buffer.alloc(8);
+  poolIdBuf.writeBigUInt64LE(BigInt(process.env.ZK_POOL_ID || '0'));
+  const [poolConfigPDA] = PublicKey.findProgramAddressSync([Buffer.from('pool_config'), poolIdBuf], PROGRAM_ID);
+  const [poolVaultPDA] = PublicKey.findProgramAddressSync([Buffer.from('pool_vault'), poolConfigPDA.toBuffer()], PROGRAM_ID);
+  // Active tree generation (PoolConfig.current_generation); the ALT must be
+  // recreated after `rollover_tree`
+  const poolConfigInfo = await connection.getAccountInfo(poolConfigPDA);
+  if (!poolConfigInfo) {
+    console.error('PoolConfig not found. Initialize the pool first.');
+    process.exit(1);
+  }
+  const generationBuf = poolConfigInfo.data.slice(287, 295); // current_generation (u64 LE)
+  const [merkleTreePDA] = PublicKey.findProgramAddressSync(
+    [Buffer.from('merkle_tree'), poolConfigPDA.toBuffer(), generationBuf],
+    PROGRAM_ID
+  );
 
   // Addresses to include in ALT
   const addresses = [
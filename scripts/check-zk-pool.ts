@@ -1,29 +1,44 @@
-import { Connection, PublicKey } from '@solana/web3.js';
+import { Connection } from '@solana/web3.js';
 import { NEXT_PUBLIC_SOLANA_RPC_URL } from '../src/lib/config';
-
-const PROGRAM_ID = new PublicKey('3CVsp1zayXhNsT8Ktrh85rTewvBJxWy8VcUtQAKdnQMb');
+import {
+  ZK_POOL_PROGRAM_ID,
+  ZK_POOL_ID,
+  getPoolConfigPDA,
+  getPoolVaultPDA,
+  getMerkleTreePDA,
+  fetchCurrentGeneration,
+  MERKLE_TREE_NEXT_INDEX_OFFSET,
+} from '../src/lib/noir-zk/relayer-core';
+
+const PROGRAM_ID = ZK_POOL_PROGRAM_ID;
 
 async function check() {
   const rpcUrl = NEXT_PUBLIC_SOLANA_RPC_URL || 'https://api.devnet.solana.com';
   const connection = new Connection(rpcUrl, 'confirmed');
 
-  const [poolConfig] = PublicKey.findProgramAddressSync([Buffer.from('pool_config')], PROGRAM_ID);
-  const [merkleTree] = PublicKey.findProgramAddressSync([Buffer.from('merkle_tree')], PROGRAM_ID);
-  const [poolVault] = PublicKey.findProgramAddressSync([Buffer.from('pool_vault')], PROGRAM_ID);
+  const [poolConfig] = getPoolConfigPDA();
+  const [poolVault] = getPoolVaultPDA();
 
   console.log('Checking ZK Pool initialization...\n');
   console.log('Program ID:', PROGRAM_ID.toBase58());
+  console.log('Pool ID:', ZK_POOL_ID.toString());
   console.log();
 
   const poolConfigInfo = await connection.getAccountInfo(poolConfig);
   console.log('PoolConfig PDA:', poolConfig.toBase58());
   console.log('  Exists:', !!poolConfigInfo);
 
+  const generation = poolConfigInfo ? await fetchCurrentGeneration(connection) : 0;
+  const [merkleTree] = getMerkleTreePDA(generation);
+  if (poolConfigInfo) {
+    console.log('  Current Generation:', generation);
+  }
+
   const merkleTreeInfo = await connection.getAccountInfo(merkleTree);
   console.log('\nMerkleTree PDA:', merkleTree.toBase58());
   console.log('  Exists:', !!merkleTreeInfo);
   if (merkleTreeInfo) {
-    const nextIndex = merkleTreeInfo.data.readBigUInt64LE(40);
+    const nextIndex = merkleTreeInfo.data.readBigUInt64LE(MERKLE_TREE_NEXT_INDEX_OFFSET);
     console.log('  Next Index:', nextIndex.toString());
   }
 
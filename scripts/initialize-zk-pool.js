@@ -35,9 +35,18 @@ const { NEXT_PUBLIC_HELIUS_API_KEY } = require('../src/lib/config');
 // Program ID for the siphon-zk-pool program
 const ZK_POOL_PROGRAM_ID = new PublicKey('3CVsp1zayXhNsT8Ktrh85rTewvBJxWy8VcUtQAKdnQMb');
 
-// Account Sizes (calculated from IDL and Anchor discriminator)
-const POOL_CONFIG_SIZE = 142; // 8 (discriminator) + 134 (fields)
-const MERKLE_TREE_SIZE = 1120; // 8 (discriminator) + 1112 (fields)
+// Pool to create; PDAs are keyed by pool id so one program can host several pools
+const POOL_ID = BigInt(process.env.ZK_POOL_ID || '0');
+// Number of recent roots accepted for withdrawals (1..=256)
+const ROOT_HISTORY_SIZE = 32;
+// 0 = one NullifierAccount PDA per nullifier
+const NULLIFIER_SHARDS = 0;
+
+function u64LE(value) {
+  const buf = Buffer.alloc(8);
+  buf.writeBigUInt64LE(BigInt(value));
+  return buf;
+}
 
 async function main() {
   console.log('=== Siphon ZK Pool Protocol Initialization ===\n');
@@ -99,20 +108,29 @@ async function main() {
     }
   }
 
-  // Derive PDAs
+  const verifierProgramId = process.env.ZK_VERIFIER_PROGRAM_ID;
+  if (!verifierProgramId) {
+    console.error('❌ ZK_VERIFIER_PROGRAM_ID not set in .env.local (Sunspot verifier for the withdrawal circuit)');
+    process.exit(1);
+  }
+  const verifierProgram = new PublicKey(verifierProgramId);
+
+  // Derive PDAs (the first tree is generation 0)
   const [poolConfigPDA] = PublicKey.findProgramAddressSync(
-    [Buffer.from('pool_config')],
+    [Buffer.from('pool_config'), u64LE(POOL_ID)],
     ZK_POOL_PROGRAM_ID
   );
   const [merkleTreePDA] = PublicKey.findProgramAddressSync(
-    [Buffer.from('merkle_tree')],
+    [Buffer.from('merkle_tree'), poolConfigPDA.toBuffer(), u64LE(0)],
     ZK_POOL_PROGRAM_ID
   );
   const [poolVaultPDA] = PublicKey.findProgramAddressSync(
-    [Buffer.from('pool_vault')],
+    [Buffer.from('pool_vault'), poolConfigPDA.toBuffer()],
     ZK_POOL_PROGRAM_ID
   );
 
+  console.log('Pool ID:', POOL_ID.toString());
+
   console.log('PoolConfig PDA:', poolConfigPDA.toBase58());
   console.log('MerkleTree PDA:', merkleTreePDA.toBase58());
   console.log('PoolVault PDA:', poolVaultPDA.toBase58());
@@ -138,25 +156,34 @@ async function main() {
     crypto.createHash('sha256').update('global:initialize').digest()
   ).slice(0, 8);
 
-  // Build instruction data: discriminator + relayer (32) + feeRecipient (32) + feeBps (2)
-  const data = Buffer.alloc(8 + 32 + 32 + 2);
-  discriminator.copy(data, 0);
-  relayer.toBuffer().copy(data, 8);
-  feeRecipient.toBuffer().copy(data, 40);
-  data.writeUInt16LE(feeBps, 72);
-
-  // Calculate rent-exempt minimum for accounts
-  const lamportsPoolConfig = await connection.getMinimumBalanceForRentExemption(POOL_CONFIG_SIZE);
-  const lamportsMerkleTree = await connection.getMinimumBalanceForRentExemption(MERKLE_TREE_SIZE);
+  // Build instruction data: discriminator + InitializeParams (Borsh):
+  // pool_id (8) + relayer (32) + fee_recipient (32) + fee_bps (2)
+  // + verifier_program (32) + root_history_size (2) + nullifier_shards (2)
+  const data = Buffer.alloc(8 + 8 + 32 + 32 + 2 + 32 + 2 + 2);
+  let offset = 0;
+  discriminator.copy(data, offset);
+  offset += 8;
+  data.writeBigUInt64LE(POOL_ID, offset);
+  offset += 8;
+  relayer.toBuffer().copy(data, offset);
+  offset += 32;
+  feeRecipient.toBuffer().copy(data, offset);
+  offset += 32;
+  data.writeUInt16LE(feeBps, offset);
+  offset += 2;
+  verifierProgram.toBuffer().copy(data, offset);
+  offset += 32;
+  data.writeUInt16LE(ROOT_HISTORY_SIZE, offset);
+  offset += 2;
+  data.writeUInt16LE(NULLIFIER_SHARDS, offset);
 
   const initializeIx = new TransactionInstruction({
     keys: [
       { pubkey: adminKeypair.publicKey, isSigner: true, isWritable: true }, // Admin
       { pubkey: poolConfigPDA, isSigner: false, isWritable: true },      // PoolConfig PDA
       { pubkey: merkleTreePDA, isSigner: false, isWritable: true },      // MerkleTree PDA
-      { pubkey: poolVaultPDA, isSigner: false, isWritable: true },       // PoolVault PDA (created implicitly or as part of PoolConfig/MerkleTree?)
+      { pubkey: poolVaultPDA, isSigner: false, isWritable: true },       // PoolVault PDA
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false }, // System Program
-      // Note: Rent sysvar is often handled implicitly by Anchor, but if explicit, add here.
     ],
     programId: ZK_POOL_PROGRAM_ID,
     data,
@@ -184,6 +211,7 @@ async function main() {
 
     console.log('\nConfig:');
     console.log('  Program ID:', ZK_POOL_PROGRAM_ID.toBase58());
+    console.log('  Pool ID:', POOL_ID.toString());
     console.log('  PoolConfig PDA:', poolConfigPDA.toBase58());
     console.log('  MerkleTree PDA:', merkleTreePDA.toBase58());
     console.log('  PoolVault PDA:', poolVaultPDA.toBase58());
@@ -191,6 +219,7 @@ async function main() {
     console.log('  Relayer:', relayer.toBase58());
     console.log('  Fee Recipient:', feeRecipient.toBase58());
     console.log('  Fee BPS:', feeBps, `(${feeBps / 100}%)`);
+    console.log('  Verifier:', verifierProgram.toBase58());
   } catch (error) {
     console.error('\n❌ Initialization failed:', error.message);
 
@@ -1,17 +1,59 @@
-/// PDA seeds
+/// PDA seeds. PoolConfig is keyed by pool id; all other pool PDAs are keyed
+/// by the PoolConfig address (trees and commitment records also by generation).
 pub const MERKLE_TREE_SEED: &[u8] = b"merkle_tree";
 pub const POOL_CONFIG_SEED: &[u8] = b"pool_config";
 pub const POOL_VAULT_SEED: &[u8] = b"pool_vault";
 pub const POOL_TOKEN_SEED: &[u8] = b"pool_token";
 pub const NULLIFIER_SEED: &[u8] = b"nullifier";
+pub const NULLIFIER_SHARD_SEED: &[u8] = b"nullifier_shard";
 pub const COMMITMENT_SEED: &[u8] = b"commitment";
+pub const MINT_CONFIG_SEED: &[u8] = b"mint_config";
+pub const WSOL_STAGING_SEED: &[u8] = b"wsol_staging";
+pub const SWAP_PROGRAM_SEED: &[u8] = b"swap_program";
 
 /// Merkle tree height (2^20 = ~1M leaves, sufficient for devnet)
 pub const MERKLE_TREE_HEIGHT: u8 = 20;
 
-/// Root history size (ring buffer of recent roots for withdrawal verification)
-/// Limited to 32 because bytemuck Pod/Zeroable only supports arrays up to 1024 bytes
-pub const ROOT_HISTORY_SIZE: usize = 32;
+/// Maximum root history size (ring buffer of recent roots for withdrawal verification).
+/// Stored as [[u8; 32]; 256], which bytemuck supports natively; each pool picks
+/// its own window up to this bound at initialization.
+pub const ROOT_HISTORY_SIZE: usize = 256;
 
 /// Maximum fee in basis points (10%)
 pub const MAX_FEE_BPS: u16 = 1000;
+
+/// Maximum number of fixed denominations a pool can accept
+pub const MAX_DENOMINATIONS: usize = 8;
+
+/// Basis points denominator (100%)
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// How long a pool must stay paused before withdrawals reopen (escape hatch): 14 days
+pub const ESCAPE_HATCH_DELAY_SECONDS: i64 = 14 * 24 * 60 * 60;
+
+/// Current WithdrawInputs layout version
+pub const WITHDRAW_INPUTS_VERSION: u8 = 3;
+
+/// Current TransactInputs layout version
+pub const TRANSACT_INPUTS_VERSION: u8 = 1;
+
+/// Current MergeInputs layout version
+pub const MERGE_INPUTS_VERSION: u8 = 1;
+
+/// Notes spent per `merge` proof (MERGE_INPUTS in the merge circuit)
+pub const MERGE_INPUTS: usize = 4;
+
+/// Current SwapInputs layout version
+pub const SWAP_INPUTS_VERSION: u8 = 1;
+
+/// Current PoolConfig layout version. Version 2 moved the reserved bytes ahead of
+/// the denominations and carved `config_sequence`, `unpaused_at` and the pending
+/// verifier from them.
+pub const POOL_CONFIG_VERSION: u8 = 2;
+
+/// Current MerkleTree layout version. Version 2 carved `sequence` from the reserved
+/// bytes.
+pub const MERKLE_TREE_VERSION: u8 = 2;
+
+/// Zeroed bytes reserved at the end of PoolConfig and MerkleTree for future fields
+pub const ACCOUNT_RESERVED_BYTES: usize = 128;
@@ -34,4 +34,61 @@ pub enum ZkPoolError {
 
     #[msg("Arithmetic overflow")]
     Overflow,
+
+    #[msg("Invalid zero-knowledge proof")]
+    InvalidProof,
+
+    #[msg("Verifier program does not match pool config")]
+    InvalidVerifierProgram,
+
+    #[msg("Value is not a valid BN254 field element")]
+    InvalidFieldElement,
+
+    #[msg("Recipient account does not match the withdrawal recipient")]
+    InvalidRecipient,
+
+    #[msg("Mint does not match the withdrawal inputs")]
+    InvalidMint,
+
+    #[msg("Unsupported withdrawal inputs version")]
+    UnsupportedInputsVersion,
+
+    #[msg("Mint is not enabled for deposits")]
+    MintNotAllowed,
+
+    #[msg("Amount is not one of the asset's denominations")]
+    InvalidDenomination,
+
+    #[msg("Root history size out of range")]
+    InvalidRootHistorySize,
+
+    #[msg("Active Merkle tree is not full")]
+    TreeNotFull,
+
+    #[msg("Nullifier account does not match the pool's nullifier mode")]
+    InvalidNullifierAccount,
+
+    #[msg("Amount exceeds a configured deposit or withdrawal limit")]
+    LimitExceeded,
+
+    #[msg("Mint has a Token-2022 extension the pool does not support")]
+    UnsupportedMintExtension,
+
+    #[msg("Swap returned less than the minimum output amount")]
+    SlippageExceeded,
+
+    #[msg("Swap moved pool funds outside the input and output token accounts")]
+    InvalidSwapAccounts,
+
+    #[msg("Verifier and limit changes are frozen while the pool is paused")]
+    ConfigFrozenWhilePaused,
+
+    #[msg("Only the depositor may close a record of the active tree")]
+    RecordStillActive,
+
+    #[msg("No verifier change is pending or its time lock has not passed")]
+    VerifierChangeNotReady,
+
+    #[msg("Account layout version is not supported by this program")]
+    UnsupportedAccountVersion,
 }
@@ -1,17 +1,70 @@
 use anchor_lang::prelude::*;
+use crate::constants::MERGE_INPUTS;
+use crate::state::{MerkleTree, PoolConfig};
+
+/// Leading fields of every event. Events from instructions that touch the active
+/// tree carry that tree's `sequence`, and config events the pool's
+/// `config_sequence`; each increases by one per event, so an indexer can detect
+/// missed or rolled-back logs per `(pool_id, generation)` and per pool.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct EventHeader {
+    pub pool_id: u64,
+    /// Tree generation the event belongs to (the active one for config events)
+    pub generation: u64,
+    /// Per-tree sequence for tree events, per-pool config sequence for config events
+    pub sequence: u64,
+    /// Slot the event was emitted in
+    pub slot: u64,
+}
+
+impl EventHeader {
+    /// Header for an event about the pool configuration; consumes the pool's next
+    /// config sequence number
+    pub fn config(pool_config: &mut PoolConfig) -> Result<Self> {
+        Ok(Self {
+            pool_id: pool_config.pool_id,
+            generation: pool_config.current_generation,
+            sequence: pool_config.next_config_sequence(),
+            slot: Clock::get()?.slot,
+        })
+    }
+
+    /// Header for an event about `tree`; consumes the tree's next sequence number
+    pub fn tree(pool_id: u64, tree: &mut MerkleTree) -> Result<Self> {
+        Ok(Self {
+            pool_id,
+            generation: tree.generation,
+            sequence: tree.next_sequence(),
+            slot: Clock::get()?.slot,
+        })
+    }
+}
 
 #[event]
 pub struct CommitmentInserted {
+    pub header: EventHeader,
     pub index: u64,
     pub commitment: [u8; 32],
     pub encrypted_output: Vec<u8>,
-    pub amount: u64,
+    /// None for change outputs and for deposits into denominated pools
+    pub amount: Option<u64>,
     /// None for SOL, Some(mint) for SPL tokens
     pub mint: Option<Pubkey>,
 }
 
+#[event]
+pub struct DepositSponsored {
+    pub header: EventHeader,
+    pub index: u64,
+    pub sponsor: Pubkey,
+    /// Tokens paid to the sponsor out of the deposit
+    pub sponsor_fee: u64,
+    pub mint: Pubkey,
+}
+
 #[event]
 pub struct WithdrawalProcessed {
+    pub header: EventHeader,
     pub nullifier_hash: [u8; 32],
     pub recipient: Pubkey,
     pub amount: u64,
@@ -22,8 +75,196 @@ pub struct WithdrawalProcessed {
     pub new_index: Option<u64>,
 }
 
+#[event]
+pub struct TransactProcessed {
+    pub header: EventHeader,
+    /// Spent nullifiers (all-zero for an unused input)
+    pub input_nullifier_hashes: [[u8; 32]; 2],
+    /// New commitments (all-zero for an unused output)
+    pub output_commitments: [[u8; 32]; 2],
+    pub output_indices: [Option<u64>; 2],
+    /// None for SOL, Some(mint) for SPL tokens
+    pub mint: Option<Pubkey>,
+}
+
+#[event]
+pub struct MergeProcessed {
+    pub header: EventHeader,
+    /// Spent nullifiers (all-zero for an unused input)
+    pub input_nullifier_hashes: [[u8; 32]; MERGE_INPUTS],
+    pub output_commitment: [u8; 32],
+    pub output_index: u64,
+    /// None for SOL, Some(mint) for SPL tokens
+    pub mint: Option<Pubkey>,
+}
+
 #[event]
 pub struct RootUpdated {
+    pub header: EventHeader,
     pub new_root: [u8; 32],
     pub root_index: u64,
+    /// Number of leaves the new root covers
+    pub leaf_count: u64,
+}
+
+#[event]
+pub struct PauseUpdated {
+    pub header: EventHeader,
+    pub paused: bool,
+    /// Admin or guardian that signed
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct GuardianUpdated {
+    pub header: EventHeader,
+    pub old_guardian: Pubkey,
+    pub new_guardian: Pubkey,
+}
+
+#[event]
+pub struct RelayerUpdated {
+    pub header: EventHeader,
+    pub old_relayer: Pubkey,
+    pub new_relayer: Pubkey,
+}
+
+#[event]
+pub struct FeeConfigUpdated {
+    pub header: EventHeader,
+    pub fee_bps: u16,
+    pub fee_recipient: Pubkey,
+}
+
+#[event]
+pub struct VerifierProgramProposed {
+    pub header: EventHeader,
+    pub pending_verifier: Pubkey,
+    /// Earliest Unix timestamp at which `apply_verifier_program` may install it
+    pub effective_at: i64,
+}
+
+#[event]
+pub struct VerifierProgramUpdated {
+    pub header: EventHeader,
+    pub old_verifier: Pubkey,
+    pub new_verifier: Pubkey,
+}
+
+#[event]
+pub struct TransactVerifierProgramUpdated {
+    pub header: EventHeader,
+    pub old_verifier: Pubkey,
+    pub new_verifier: Pubkey,
+}
+
+#[event]
+pub struct MergeVerifierProgramUpdated {
+    pub header: EventHeader,
+    pub old_verifier: Pubkey,
+    pub new_verifier: Pubkey,
+}
+
+#[event]
+pub struct AdminProposed {
+    pub header: EventHeader,
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+}
+
+#[event]
+pub struct AdminTransferred {
+    pub header: EventHeader,
+    pub old_admin: Pubkey,
+    pub new_admin: Pubkey,
+}
+
+#[event]
+pub struct MintRegistered {
+    pub header: EventHeader,
+    pub mint: Pubkey,
+    pub min_deposit: u64,
+    pub max_deposit: u64,
+    pub fee_bps: u16,
+    pub min_fee: u64,
+}
+
+#[event]
+pub struct MintEnabledUpdated {
+    pub header: EventHeader,
+    pub mint: Pubkey,
+    pub enabled: bool,
+}
+
+#[event]
+pub struct MintFeeConfigUpdated {
+    pub header: EventHeader,
+    pub mint: Pubkey,
+    pub fee_bps: u16,
+    pub min_fee: u64,
+}
+
+#[event]
+pub struct LimitsUpdated {
+    pub header: EventHeader,
+    /// None for SOL, Some(mint) for SPL tokens
+    pub mint: Option<Pubkey>,
+    pub min_deposit: u64,
+    pub max_deposit: u64,
+    pub max_withdrawal: u64,
+    pub epoch_withdrawal_cap: u64,
+}
+
+#[event]
+pub struct DenominationsUpdated {
+    pub header: EventHeader,
+    /// None for SOL, Some(mint) for SPL tokens
+    pub mint: Option<Pubkey>,
+    pub denominations: Vec<u64>,
+}
+
+#[event]
+pub struct CommitmentRecordClosed {
+    pub header: EventHeader,
+    pub generation: u64,
+    pub index: u64,
+    pub depositor: Pubkey,
+}
+
+#[event]
+pub struct TreeRolledOver {
+    pub header: EventHeader,
+    pub old_generation: u64,
+    pub new_generation: u64,
+    pub new_tree: Pubkey,
+}
+
+#[event]
+pub struct SwapProgramUpdated {
+    pub header: EventHeader,
+    pub program: Pubkey,
+    pub enabled: bool,
+}
+
+#[event]
+pub struct SwapProcessed {
+    pub header: EventHeader,
+    pub nullifier_hash: [u8; 32],
+    pub swap_program: Pubkey,
+    pub input_mint: Pubkey,
+    pub amount_in: u64,
+    pub fee: u64,
+    pub output_mint: Pubkey,
+    pub amount_out: u64,
+    pub output_index: u64,
+    /// Change note of the input asset (if partial spend)
+    pub change_index: Option<u64>,
+}
+
+#[event]
+pub struct AccountMigrated {
+    pub header: EventHeader,
+    pub account: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
 }
@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+use crate::state::PoolConfig;
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::events::{EventHeader, AdminTransferred};
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    /// Proposed admin — must sign to complete the handover
+    pub new_admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
+        bump = pool_config.bump,
+        constraint = pool_config.pending_admin != Pubkey::default() @ ZkPoolError::UnauthorizedAdmin,
+        constraint = pool_config.pending_admin == new_admin.key() @ ZkPoolError::UnauthorizedAdmin,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+}
+
+pub fn handler(ctx: Context<AcceptAdmin>) -> Result<()> {
+    let config = &mut ctx.accounts.pool_config;
+    let old_admin = config.admin;
+    config.admin = ctx.accounts.new_admin.key();
+    config.pending_admin = Pubkey::default();
+
+    emit!(AdminTransferred {
+        header: EventHeader::config(config)?,
+        old_admin,
+        new_admin: config.admin,
+    });
+
+    msg!("Admin transferred: {} -> {}", old_admin, config.admin);
+
+    Ok(())
+}
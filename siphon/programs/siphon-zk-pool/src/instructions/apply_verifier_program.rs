@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+use crate::state::PoolConfig;
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::events::{EventHeader, VerifierProgramUpdated};
+
+#[derive(Accounts)]
+pub struct ApplyVerifierProgram<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
+        bump = pool_config.bump,
+        constraint = pool_config.admin == admin.key() @ ZkPoolError::UnauthorizedAdmin,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+}
+
+/// Install the verifier proposed by `set_verifier_program` once its time lock has passed
+pub fn handler(ctx: Context<ApplyVerifierProgram>) -> Result<()> {
+    let config = &mut ctx.accounts.pool_config;
+    let old_verifier = config.apply_verifier(Clock::get()?.unix_timestamp)?;
+    let new_verifier = config.verifier_program;
+
+    emit!(VerifierProgramUpdated {
+        header: EventHeader::config(config)?,
+        old_verifier,
+        new_verifier,
+    });
+
+    msg!("Verifier program updated: {}", new_verifier);
+
+    Ok(())
+}
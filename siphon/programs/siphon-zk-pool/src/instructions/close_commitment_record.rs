@@ -0,0 +1,72 @@
+use anchor_lang::prelude::*;
+use crate::state::{CommitmentRecord, MerkleTree, PoolConfig};
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::events::{EventHeader, CommitmentRecordClosed};
+
+#[derive(Accounts)]
+pub struct CloseCommitmentRecord<'info> {
+    /// The depositor, or anyone once the record's tree is rolled over; rent always
+    /// goes back to the depositor
+    pub closer: Signer<'info>,
+
+    #[account(
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    /// Tree holding the record's leaf; its sequence numbers the close event
+    #[account(
+        mut,
+        seeds = [
+            MERKLE_TREE_SEED,
+            pool_config.key().as_ref(),
+            &commitment_record.generation.to_le_bytes(),
+        ],
+        bump = merkle_tree.load()?.bump,
+    )]
+    pub merkle_tree: AccountLoader<'info, MerkleTree>,
+
+    /// Depositor that paid for the record
+    /// CHECK: Must match `commitment_record.depositor`; only receives lamports
+    #[account(
+        mut,
+        constraint = depositor.key() == commitment_record.depositor @ ZkPoolError::InvalidRecipient,
+    )]
+    pub depositor: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        close = depositor,
+        constraint = commitment_record.can_close(&closer.key(), pool_config.current_generation)
+            @ ZkPoolError::RecordStillActive,
+        seeds = [
+            COMMITMENT_SEED,
+            pool_config.key().as_ref(),
+            &commitment_record.generation.to_le_bytes(),
+            &commitment_record.index.to_le_bytes(),
+        ],
+        bump = commitment_record.bump,
+    )]
+    pub commitment_record: Account<'info, CommitmentRecord>,
+}
+
+/// Close a commitment record. The leaf is inserted into the on-chain tree at
+/// deposit time, so the record is only an index aid; clients fall back to
+/// `CommitmentInserted` events once it is gone.
+pub fn handler(ctx: Context<CloseCommitmentRecord>) -> Result<()> {
+    let record = &ctx.accounts.commitment_record;
+    let mut tree = ctx.accounts.merkle_tree.load_mut()?;
+
+    emit!(CommitmentRecordClosed {
+        header: EventHeader::tree(ctx.accounts.pool_config.pool_id, &mut tree)?,
+        generation: record.generation,
+        index: record.index,
+        depositor: record.depositor,
+    });
+
+    msg!("Commitment record {}:{} closed", record.generation, record.index);
+
+    Ok(())
+}
@@ -3,23 +3,29 @@ use anchor_lang::system_program;
 use crate::state::{MerkleTree, PoolConfig, CommitmentRecord};
 use crate::constants::*;
 use crate::errors::ZkPoolError;
-use crate::events::CommitmentInserted;
+use crate::notes::{emit_root_updated, insert_note, Note};
+use crate::poseidon;
+use crate::verifier::asset_id;
 
 #[derive(Accounts)]
-#[instruction(commitment: [u8; 32], encrypted_output: Vec<u8>, amount: u64, leaf_index: u64)]
+#[instruction(precommitment: [u8; 32], encrypted_output: Vec<u8>, amount: u64, leaf_index: u64)]
 pub struct DepositSol<'info> {
     #[account(mut)]
     pub depositor: Signer<'info>,
 
     #[account(
-        seeds = [POOL_CONFIG_SEED],
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
         bump = pool_config.bump,
     )]
     pub pool_config: Account<'info, PoolConfig>,
 
     #[account(
         mut,
-        seeds = [MERKLE_TREE_SEED],
+        seeds = [
+            MERKLE_TREE_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.current_generation.to_le_bytes(),
+        ],
         bump = pool_config.tree_bump,
     )]
     pub merkle_tree: AccountLoader<'info, MerkleTree>,
@@ -28,17 +34,22 @@ pub struct DepositSol<'info> {
     /// CHECK: PDA that holds lamports
     #[account(
         mut,
-        seeds = [POOL_VAULT_SEED],
+        seeds = [POOL_VAULT_SEED, pool_config.key().as_ref()],
         bump = pool_config.vault_bump,
     )]
     pub pool_vault: SystemAccount<'info>,
 
-    /// Commitment record for this deposit (keyed by leaf_index)
+    /// Commitment record for this deposit (keyed by generation and leaf_index)
     #[account(
         init,
         payer = depositor,
         space = 8 + CommitmentRecord::INIT_SPACE,
-        seeds = [COMMITMENT_SEED, &leaf_index.to_le_bytes()],
+        seeds = [
+            COMMITMENT_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.current_generation.to_le_bytes(),
+            &leaf_index.to_le_bytes(),
+        ],
         bump
     )]
     pub commitment_record: Account<'info, CommitmentRecord>,
@@ -48,14 +59,19 @@ pub struct DepositSol<'info> {
 
 pub fn handler(
     ctx: Context<DepositSol>,
-    commitment: [u8; 32],
+    precommitment: [u8; 32],
     encrypted_output: Vec<u8>,
     amount: u64,
     leaf_index: u64,
 ) -> Result<()> {
     require!(!ctx.accounts.pool_config.paused, ZkPoolError::ProtocolPaused);
     require!(amount > 0, ZkPoolError::InvalidAmount);
-    require!(commitment != [0u8; 32], ZkPoolError::InvalidCommitment);
+    require!(
+        ctx.accounts.pool_config.is_allowed_deposit(amount),
+        ZkPoolError::InvalidDenomination
+    );
+    ctx.accounts.pool_config.sol_limits.check_deposit(amount)?;
+    require!(precommitment != [0u8; 32], ZkPoolError::InvalidCommitment);
 
     let mut tree = ctx.accounts.merkle_tree.load_mut()?;
     require!(!tree.is_full(), ZkPoolError::TreeFull);
@@ -75,25 +91,28 @@ pub fn handler(
         amount,
     )?;
 
-    // Store commitment record
-    let record = &mut ctx.accounts.commitment_record;
-    record.index = leaf_index;
-    record.commitment = commitment;
-    record.bump = ctx.bumps.commitment_record;
+    // Bind the note to the deposited amount and asset
+    let commitment = poseidon::commitment(amount, &asset_id(&Pubkey::default()), &precommitment)?;
 
-    // Increment leaf index
-    tree.next_index = leaf_index.checked_add(1).ok_or(ZkPoolError::Overflow)?;
-
-    // Emit event for relayer indexing
-    emit!(CommitmentInserted {
-        index: leaf_index,
-        commitment,
-        encrypted_output,
-        amount,
-        mint: None,
-    });
+    // Append the note and publish it for relayer indexing
+    let event_amount = ctx.accounts.pool_config.deposit_event_amount(amount);
+    let pool_id = ctx.accounts.pool_config.pool_id;
+    insert_note(
+        pool_id,
+        &mut tree,
+        &mut ctx.accounts.commitment_record,
+        ctx.accounts.depositor.key(),
+        ctx.bumps.commitment_record,
+        Note {
+            commitment,
+            encrypted_output,
+            amount: event_amount,
+            mint: None,
+        },
+    )?;
+    emit_root_updated(pool_id, &mut tree)?;
 
-    msg!("SOL deposit: index={}, amount={}", leaf_index, amount);
+    msg!("SOL deposit: index={}, amount={:?}", leaf_index, event_amount);
 
     Ok(())
 }
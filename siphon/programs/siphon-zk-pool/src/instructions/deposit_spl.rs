@@ -1,32 +1,47 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 use anchor_spl::associated_token::AssociatedToken;
-use crate::state::{MerkleTree, PoolConfig, CommitmentRecord};
+use crate::state::{MerkleTree, PoolConfig, CommitmentRecord, MintConfig};
 use crate::constants::*;
 use crate::errors::ZkPoolError;
-use crate::events::CommitmentInserted;
+use crate::notes::{emit_root_updated, insert_note, Note};
+use crate::poseidon;
+use crate::tokens;
+use crate::verifier::asset_id;
 
 #[derive(Accounts)]
-#[instruction(commitment: [u8; 32], encrypted_output: Vec<u8>, amount: u64, leaf_index: u64)]
+#[instruction(precommitment: [u8; 32], encrypted_output: Vec<u8>, amount: u64, leaf_index: u64)]
 pub struct DepositSpl<'info> {
     #[account(mut)]
     pub depositor: Signer<'info>,
 
     #[account(
-        seeds = [POOL_CONFIG_SEED],
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
         bump = pool_config.bump,
     )]
     pub pool_config: Account<'info, PoolConfig>,
 
     #[account(
         mut,
-        seeds = [MERKLE_TREE_SEED],
+        seeds = [
+            MERKLE_TREE_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.current_generation.to_le_bytes(),
+        ],
         bump = pool_config.tree_bump,
     )]
     pub merkle_tree: AccountLoader<'info, MerkleTree>,
 
-    /// SPL token mint (e.g., USDC)
-    pub token_mint: Account<'info, Mint>,
+    /// SPL Token or Token-2022 mint (e.g., USDC, PYUSD)
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Allow-list entry for this mint
+    #[account(
+        seeds = [MINT_CONFIG_SEED, pool_config.key().as_ref(), token_mint.key().as_ref()],
+        bump = mint_config.bump,
+        constraint = mint_config.enabled @ ZkPoolError::MintNotAllowed,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
 
     /// Depositor's token account
     #[account(
@@ -34,7 +49,7 @@ pub struct DepositSpl<'info> {
         constraint = depositor_token_account.mint == token_mint.key(),
         constraint = depositor_token_account.owner == depositor.key(),
     )]
-    pub depositor_token_account: Account<'info, TokenAccount>,
+    pub depositor_token_account: InterfaceAccount<'info, TokenAccount>,
 
     /// Pool's token account for this mint
     #[account(
@@ -42,13 +57,14 @@ pub struct DepositSpl<'info> {
         payer = depositor,
         associated_token::mint = token_mint,
         associated_token::authority = pool_vault,
+        associated_token::token_program = token_program,
     )]
-    pub pool_token_account: Account<'info, TokenAccount>,
+    pub pool_token_account: InterfaceAccount<'info, TokenAccount>,
 
     /// Pool vault (authority for token accounts)
     /// CHECK: PDA used as authority
     #[account(
-        seeds = [POOL_VAULT_SEED],
+        seeds = [POOL_VAULT_SEED, pool_config.key().as_ref()],
         bump = pool_config.vault_bump,
     )]
     pub pool_vault: SystemAccount<'info>,
@@ -58,65 +74,76 @@ pub struct DepositSpl<'info> {
         init,
         payer = depositor,
         space = 8 + CommitmentRecord::INIT_SPACE,
-        seeds = [COMMITMENT_SEED, &leaf_index.to_le_bytes()],
+        seeds = [
+            COMMITMENT_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.current_generation.to_le_bytes(),
+            &leaf_index.to_le_bytes(),
+        ],
         bump
     )]
     pub commitment_record: Account<'info, CommitmentRecord>,
 
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
 pub fn handler(
     ctx: Context<DepositSpl>,
-    commitment: [u8; 32],
+    precommitment: [u8; 32],
     encrypted_output: Vec<u8>,
     amount: u64,
     leaf_index: u64,
 ) -> Result<()> {
     require!(!ctx.accounts.pool_config.paused, ZkPoolError::ProtocolPaused);
     require!(amount > 0, ZkPoolError::InvalidAmount);
-    require!(commitment != [0u8; 32], ZkPoolError::InvalidCommitment);
+    require!(precommitment != [0u8; 32], ZkPoolError::InvalidCommitment);
 
     let mut tree = ctx.accounts.merkle_tree.load_mut()?;
     require!(!tree.is_full(), ZkPoolError::TreeFull);
     require!(leaf_index == tree.next_index, ZkPoolError::InvalidAmount);
 
-    // Transfer SPL tokens from depositor to pool token account
-    token::transfer(
-        CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.depositor_token_account.to_account_info(),
-                to: ctx.accounts.pool_token_account.to_account_info(),
-                authority: ctx.accounts.depositor.to_account_info(),
-            },
-        ),
+    // Transfer tokens from depositor to pool token account. The note is worth what
+    // the pool received, net of any Token-2022 transfer fee.
+    let amount = tokens::transfer_in(
+        &ctx.accounts.token_program.to_account_info(),
+        &ctx.accounts.depositor_token_account,
+        &mut ctx.accounts.pool_token_account,
+        &ctx.accounts.token_mint,
+        &ctx.accounts.depositor.to_account_info(),
         amount,
     )?;
-
-    // Store commitment record
-    let record = &mut ctx.accounts.commitment_record;
-    record.index = leaf_index;
-    record.commitment = commitment;
-    record.bump = ctx.bumps.commitment_record;
-
-    // Increment leaf index
-    tree.next_index = leaf_index.checked_add(1).ok_or(ZkPoolError::Overflow)?;
+    require!(
+        ctx.accounts.mint_config.is_allowed_deposit(amount),
+        ZkPoolError::InvalidDenomination
+    );
+    ctx.accounts.mint_config.limits.check_deposit(amount)?;
 
     let mint_key = ctx.accounts.token_mint.key();
 
-    // Emit event for relayer indexing
-    emit!(CommitmentInserted {
-        index: leaf_index,
-        commitment,
-        encrypted_output,
-        amount,
-        mint: Some(mint_key),
-    });
+    // Bind the note to the deposited amount and mint
+    let commitment = poseidon::commitment(amount, &asset_id(&mint_key), &precommitment)?;
+
+    // Append the note and publish it for relayer indexing
+    let event_amount = ctx.accounts.mint_config.deposit_event_amount(amount);
+    let pool_id = ctx.accounts.pool_config.pool_id;
+    insert_note(
+        pool_id,
+        &mut tree,
+        &mut ctx.accounts.commitment_record,
+        ctx.accounts.depositor.key(),
+        ctx.bumps.commitment_record,
+        Note {
+            commitment,
+            encrypted_output,
+            amount: event_amount,
+            mint: Some(mint_key),
+        },
+    )?;
+    emit_root_updated(pool_id, &mut tree)?;
 
-    msg!("SPL deposit: index={}, amount={}, mint={}", leaf_index, amount, mint_key);
+    msg!("SPL deposit: index={}, amount={:?}, mint={}", leaf_index, event_amount, mint_key);
 
     Ok(())
 }
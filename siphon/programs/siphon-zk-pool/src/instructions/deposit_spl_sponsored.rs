@@ -0,0 +1,202 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use anchor_spl::associated_token::AssociatedToken;
+use crate::state::{MerkleTree, PoolConfig, CommitmentRecord, MintConfig};
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::events::{DepositSponsored, EventHeader};
+use crate::notes::{emit_root_updated, insert_note, Note};
+use crate::poseidon;
+use crate::tokens;
+use crate::verifier::asset_id;
+
+#[derive(Accounts)]
+#[instruction(precommitment: [u8; 32], encrypted_output: Vec<u8>, amount: u64, leaf_index: u64)]
+pub struct DepositSplSponsored<'info> {
+    /// Token owner; only authorizes the token transfers
+    pub depositor: Signer<'info>,
+
+    /// Pays rent (and usually the transaction fee) on the depositor's behalf
+    #[account(mut)]
+    pub sponsor: Signer<'info>,
+
+    #[account(
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(
+        mut,
+        seeds = [
+            MERKLE_TREE_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.current_generation.to_le_bytes(),
+        ],
+        bump = pool_config.tree_bump,
+    )]
+    pub merkle_tree: AccountLoader<'info, MerkleTree>,
+
+    /// SPL Token or Token-2022 mint (e.g., USDC, PYUSD)
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Allow-list entry for this mint
+    #[account(
+        seeds = [MINT_CONFIG_SEED, pool_config.key().as_ref(), token_mint.key().as_ref()],
+        bump = mint_config.bump,
+        constraint = mint_config.enabled @ ZkPoolError::MintNotAllowed,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    /// Depositor's token account
+    #[account(
+        mut,
+        constraint = depositor_token_account.mint == token_mint.key(),
+        constraint = depositor_token_account.owner == depositor.key(),
+    )]
+    pub depositor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Sponsor's token account; receives `sponsor_fee` (required when it is non-zero)
+    #[account(
+        mut,
+        constraint = sponsor_token_account.mint == token_mint.key(),
+        constraint = sponsor_token_account.owner == sponsor.key(),
+    )]
+    pub sponsor_token_account: Option<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Pool's token account for this mint
+    #[account(
+        init_if_needed,
+        payer = sponsor,
+        associated_token::mint = token_mint,
+        associated_token::authority = pool_vault,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Pool vault (authority for token accounts)
+    /// CHECK: PDA used as authority
+    #[account(
+        seeds = [POOL_VAULT_SEED, pool_config.key().as_ref()],
+        bump = pool_config.vault_bump,
+    )]
+    pub pool_vault: SystemAccount<'info>,
+
+    /// Commitment record for this deposit; rent refunds go to the sponsor
+    #[account(
+        init,
+        payer = sponsor,
+        space = 8 + CommitmentRecord::INIT_SPACE,
+        seeds = [
+            COMMITMENT_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.current_generation.to_le_bytes(),
+            &leaf_index.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub commitment_record: Account<'info, CommitmentRecord>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Deposit SPL tokens with a sponsor covering rent. `amount` is debited from the
+/// depositor; `sponsor_fee` of it reimburses the sponsor and the rest becomes the note.
+pub fn handler(
+    ctx: Context<DepositSplSponsored>,
+    precommitment: [u8; 32],
+    encrypted_output: Vec<u8>,
+    amount: u64,
+    leaf_index: u64,
+    sponsor_fee: u64,
+) -> Result<()> {
+    let value = amount.checked_sub(sponsor_fee).ok_or(ZkPoolError::InvalidAmount)?;
+
+    require!(!ctx.accounts.pool_config.paused, ZkPoolError::ProtocolPaused);
+    require!(value > 0, ZkPoolError::InvalidAmount);
+    require!(precommitment != [0u8; 32], ZkPoolError::InvalidCommitment);
+
+    let mut tree = ctx.accounts.merkle_tree.load_mut()?;
+    require!(!tree.is_full(), ZkPoolError::TreeFull);
+    require!(leaf_index == tree.next_index, ZkPoolError::InvalidAmount);
+
+    // Transfer the note value from depositor to pool token account, crediting what
+    // the pool received net of any Token-2022 transfer fee
+    let value = tokens::transfer_in(
+        &ctx.accounts.token_program.to_account_info(),
+        &ctx.accounts.depositor_token_account,
+        &mut ctx.accounts.pool_token_account,
+        &ctx.accounts.token_mint,
+        &ctx.accounts.depositor.to_account_info(),
+        value,
+    )?;
+    require!(
+        ctx.accounts.mint_config.is_allowed_deposit(value),
+        ZkPoolError::InvalidDenomination
+    );
+    ctx.accounts.mint_config.limits.check_deposit(value)?;
+
+    // Reimburse the sponsor
+    if sponsor_fee > 0 {
+        let sponsor_token_account = ctx
+            .accounts
+            .sponsor_token_account
+            .as_ref()
+            .ok_or(ZkPoolError::InvalidFeeConfig)?;
+        token_interface::transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.depositor_token_account.to_account_info(),
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    to: sponsor_token_account.to_account_info(),
+                    authority: ctx.accounts.depositor.to_account_info(),
+                },
+            ),
+            sponsor_fee,
+            ctx.accounts.token_mint.decimals,
+        )?;
+    }
+
+    let mint_key = ctx.accounts.token_mint.key();
+
+    // Bind the note to the deposited value and mint
+    let commitment = poseidon::commitment(value, &asset_id(&mint_key), &precommitment)?;
+
+    // Append the note and publish it for relayer indexing
+    let event_amount = ctx.accounts.mint_config.deposit_event_amount(value);
+    let pool_id = ctx.accounts.pool_config.pool_id;
+    insert_note(
+        pool_id,
+        &mut tree,
+        &mut ctx.accounts.commitment_record,
+        ctx.accounts.sponsor.key(),
+        ctx.bumps.commitment_record,
+        Note {
+            commitment,
+            encrypted_output,
+            amount: event_amount,
+            mint: Some(mint_key),
+        },
+    )?;
+    emit_root_updated(pool_id, &mut tree)?;
+
+    emit!(DepositSponsored {
+        header: EventHeader::tree(ctx.accounts.pool_config.pool_id, &mut tree)?,
+        index: leaf_index,
+        sponsor: ctx.accounts.sponsor.key(),
+        sponsor_fee,
+        mint: mint_key,
+    });
+
+    msg!(
+        "Sponsored SPL deposit: index={}, amount={:?}, mint={}",
+        leaf_index,
+        event_amount,
+        mint_key
+    );
+
+    Ok(())
+}
@@ -0,0 +1,166 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token::{self, spl_token, CloseAccount, Mint, Token, TokenAccount, TransferChecked};
+use crate::state::{MerkleTree, PoolConfig, CommitmentRecord};
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::notes::{emit_root_updated, insert_note, Note};
+use crate::poseidon;
+use crate::verifier::asset_id;
+
+#[derive(Accounts)]
+#[instruction(precommitment: [u8; 32], encrypted_output: Vec<u8>, amount: u64, leaf_index: u64)]
+pub struct DepositWsol<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    #[account(
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(
+        mut,
+        seeds = [
+            MERKLE_TREE_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.current_generation.to_le_bytes(),
+        ],
+        bump = pool_config.tree_bump,
+    )]
+    pub merkle_tree: AccountLoader<'info, MerkleTree>,
+
+    /// Pool vault that receives the unwrapped SOL
+    /// CHECK: PDA that holds lamports
+    #[account(
+        mut,
+        seeds = [POOL_VAULT_SEED, pool_config.key().as_ref()],
+        bump = pool_config.vault_bump,
+    )]
+    pub pool_vault: SystemAccount<'info>,
+
+    #[account(address = spl_token::native_mint::ID @ ZkPoolError::InvalidMint)]
+    pub native_mint: Account<'info, Mint>,
+
+    /// Depositor's wSOL account
+    #[account(
+        mut,
+        constraint = depositor_token_account.mint == native_mint.key(),
+        constraint = depositor_token_account.owner == depositor.key(),
+    )]
+    pub depositor_token_account: Account<'info, TokenAccount>,
+
+    /// Scratch wSOL account, created and closed within the instruction to unwrap `amount`
+    #[account(
+        init,
+        payer = depositor,
+        seeds = [WSOL_STAGING_SEED, pool_config.key().as_ref()],
+        bump,
+        token::mint = native_mint,
+        token::authority = depositor,
+    )]
+    pub wsol_staging: Account<'info, TokenAccount>,
+
+    /// Commitment record for this deposit (keyed by generation and leaf_index)
+    #[account(
+        init,
+        payer = depositor,
+        space = 8 + CommitmentRecord::INIT_SPACE,
+        seeds = [
+            COMMITMENT_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.current_generation.to_le_bytes(),
+            &leaf_index.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub commitment_record: Account<'info, CommitmentRecord>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Deposit wSOL as a SOL note: `amount` is unwrapped into the pool vault, so the
+/// note is indistinguishable from a `deposit_sol` note and can be withdrawn either way.
+pub fn handler(
+    ctx: Context<DepositWsol>,
+    precommitment: [u8; 32],
+    encrypted_output: Vec<u8>,
+    amount: u64,
+    leaf_index: u64,
+) -> Result<()> {
+    require!(!ctx.accounts.pool_config.paused, ZkPoolError::ProtocolPaused);
+    require!(amount > 0, ZkPoolError::InvalidAmount);
+    require!(
+        ctx.accounts.pool_config.is_allowed_deposit(amount),
+        ZkPoolError::InvalidDenomination
+    );
+    ctx.accounts.pool_config.sol_limits.check_deposit(amount)?;
+    require!(precommitment != [0u8; 32], ZkPoolError::InvalidCommitment);
+
+    let mut tree = ctx.accounts.merkle_tree.load_mut()?;
+    require!(!tree.is_full(), ZkPoolError::TreeFull);
+    require!(leaf_index == tree.next_index, ZkPoolError::InvalidAmount);
+
+    // Unwrap: move `amount` wSOL into the scratch account, then close it back to the
+    // depositor, who ends up holding `amount` more SOL (plus the returned rent)
+    token::transfer_checked(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            TransferChecked {
+                from: ctx.accounts.depositor_token_account.to_account_info(),
+                mint: ctx.accounts.native_mint.to_account_info(),
+                to: ctx.accounts.wsol_staging.to_account_info(),
+                authority: ctx.accounts.depositor.to_account_info(),
+            },
+        ),
+        amount,
+        ctx.accounts.native_mint.decimals,
+    )?;
+    token::close_account(CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        CloseAccount {
+            account: ctx.accounts.wsol_staging.to_account_info(),
+            destination: ctx.accounts.depositor.to_account_info(),
+            authority: ctx.accounts.depositor.to_account_info(),
+        },
+    ))?;
+
+    // Transfer the unwrapped SOL to the pool vault
+    system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            system_program::Transfer {
+                from: ctx.accounts.depositor.to_account_info(),
+                to: ctx.accounts.pool_vault.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    // wSOL notes share SOL's asset id
+    let commitment = poseidon::commitment(amount, &asset_id(&Pubkey::default()), &precommitment)?;
+
+    // Append the note and publish it for relayer indexing
+    let event_amount = ctx.accounts.pool_config.deposit_event_amount(amount);
+    let pool_id = ctx.accounts.pool_config.pool_id;
+    insert_note(
+        pool_id,
+        &mut tree,
+        &mut ctx.accounts.commitment_record,
+        ctx.accounts.depositor.key(),
+        ctx.bumps.commitment_record,
+        Note {
+            commitment,
+            encrypted_output,
+            amount: event_amount,
+            mint: None,
+        },
+    )?;
+    emit_root_updated(pool_id, &mut tree)?;
+
+    msg!("wSOL deposit: index={}, amount={:?}", leaf_index, event_amount);
+
+    Ok(())
+}
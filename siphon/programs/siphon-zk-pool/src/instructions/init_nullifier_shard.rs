@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+use crate::state::{NullifierShard, PoolConfig};
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+
+#[derive(Accounts)]
+#[instruction(shard: u16)]
+pub struct InitNullifierShard<'info> {
+    /// Anyone may create a shard; pays rent for the header
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
+        bump = pool_config.bump,
+        constraint = shard < pool_config.nullifier_shards @ ZkPoolError::InvalidNullifierAccount,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + std::mem::size_of::<NullifierShard>(),
+        seeds = [NULLIFIER_SHARD_SEED, pool_config.key().as_ref(), &shard.to_le_bytes()],
+        bump
+    )]
+    pub nullifier_shard: AccountLoader<'info, NullifierShard>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Create an empty nullifier shard for a pool in sharded mode
+pub fn handler(ctx: Context<InitNullifierShard>, shard: u16) -> Result<()> {
+    let mut nullifier_shard = ctx.accounts.nullifier_shard.load_init()?;
+    nullifier_shard.pool = ctx.accounts.pool_config.key();
+    nullifier_shard.count = 0;
+    nullifier_shard.shard = shard;
+    nullifier_shard.bump = ctx.bumps.nullifier_shard;
+
+    msg!("Nullifier shard {} initialized", shard);
+
+    Ok(())
+}
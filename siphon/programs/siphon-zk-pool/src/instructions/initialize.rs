@@ -1,9 +1,28 @@
 use anchor_lang::prelude::*;
-use crate::state::{MerkleTree, PoolConfig};
+use crate::state::{MerkleTree, PoolConfig, RateLimits};
 use crate::constants::*;
 use crate::errors::ZkPoolError;
 
+/// Pool creation parameters
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct InitializeParams {
+    /// Pool identifier (PoolConfig PDA seed)
+    pub pool_id: u64,
+    /// Pool relayer; fees it collects must go to `fee_recipient`
+    pub relayer: Pubkey,
+    pub fee_recipient: Pubkey,
+    /// Withdrawal fee in basis points (at most MAX_FEE_BPS)
+    pub fee_bps: u16,
+    /// Sunspot verifier program for the withdrawal circuit
+    pub verifier_program: Pubkey,
+    /// Number of recent roots accepted for withdrawals (1..=ROOT_HISTORY_SIZE)
+    pub root_history_size: u16,
+    /// Nullifier shards (0 = one NullifierAccount PDA per nullifier)
+    pub nullifier_shards: u16,
+}
+
 #[derive(Accounts)]
+#[instruction(params: InitializeParams)]
 pub struct Initialize<'info> {
     #[account(mut)]
     pub admin: Signer<'info>,
@@ -12,7 +31,7 @@ pub struct Initialize<'info> {
         init,
         payer = admin,
         space = 8 + PoolConfig::INIT_SPACE,
-        seeds = [POOL_CONFIG_SEED],
+        seeds = [POOL_CONFIG_SEED, &params.pool_id.to_le_bytes()],
         bump
     )]
     pub pool_config: Account<'info, PoolConfig>,
@@ -22,7 +41,7 @@ pub struct Initialize<'info> {
         init,
         payer = admin,
         space = 8 + std::mem::size_of::<MerkleTree>(),
-        seeds = [MERKLE_TREE_SEED],
+        seeds = [MERKLE_TREE_SEED, pool_config.key().as_ref(), &0u64.to_le_bytes()],
         bump
     )]
     pub merkle_tree: AccountLoader<'info, MerkleTree>,
@@ -31,7 +50,7 @@ pub struct Initialize<'info> {
     /// CHECK: This is a PDA that holds lamports, not a data account
     #[account(
         mut,
-        seeds = [POOL_VAULT_SEED],
+        seeds = [POOL_VAULT_SEED, pool_config.key().as_ref()],
         bump
     )]
     pub pool_vault: SystemAccount<'info>,
@@ -39,42 +58,69 @@ pub struct Initialize<'info> {
     pub system_program: Program<'info, System>,
 }
 
-pub fn handler(
-    ctx: Context<Initialize>,
-    relayer: Pubkey,
-    fee_recipient: Pubkey,
-    fee_bps: u16,
-) -> Result<()> {
+pub fn handler(ctx: Context<Initialize>, params: InitializeParams) -> Result<()> {
+    let InitializeParams {
+        pool_id,
+        relayer,
+        fee_recipient,
+        fee_bps,
+        verifier_program,
+        root_history_size,
+        nullifier_shards,
+    } = params;
+
     require!(fee_bps <= MAX_FEE_BPS, ZkPoolError::InvalidFeeConfig);
+    require!(
+        root_history_size > 0 && root_history_size as usize <= ROOT_HISTORY_SIZE,
+        ZkPoolError::InvalidRootHistorySize
+    );
 
     // Initialize pool config
     let config = &mut ctx.accounts.pool_config;
+    config.version = POOL_CONFIG_VERSION;
+    config.pool_id = pool_id;
     config.admin = ctx.accounts.admin.key();
+    config.pending_admin = Pubkey::default();
     config.relayer = relayer;
     config.fee_bps = fee_bps;
     config.fee_recipient = fee_recipient;
-    config.verifier_program = Pubkey::default();
+    config.verifier_program = verifier_program;
+    config.transact_verifier_program = Pubkey::default();
+    config.merge_verifier_program = Pubkey::default();
+    config.guardian = Pubkey::default();
     config.paused = false;
+    config.paused_at = 0;
     config.bump = ctx.bumps.pool_config;
     config.tree_bump = ctx.bumps.merkle_tree;
     config.vault_bump = ctx.bumps.pool_vault;
+    config.current_generation = 0;
+    config.nullifier_shards = nullifier_shards;
+    config.sol_limits = RateLimits::unlimited();
+    config.sol_denominations = Vec::new();
 
     // Initialize Merkle tree
     let mut tree = ctx.accounts.merkle_tree.load_init()?;
     tree.authority = relayer;
+    tree.generation = 0;
     tree.next_index = 0;
     tree.current_root = [0u8; 32];
-    tree.root_history = [0u8; 1024];
+    // root_history is left zeroed by account creation (too large to build on the stack)
     tree.root_history_index = 0;
     tree.height = MERKLE_TREE_HEIGHT;
     tree.bump = ctx.bumps.merkle_tree;
-    tree._padding = [0u8; 6];
+    tree.root_history_size = root_history_size;
+    tree.version = MERKLE_TREE_VERSION;
+    tree._padding = [0u8; 3];
+    tree.init_zeros()?;
 
-    msg!("ZK Pool initialized");
+    msg!("ZK Pool {} initialized", pool_id);
     msg!("Admin: {}", ctx.accounts.admin.key());
     msg!("Relayer: {}", relayer);
     msg!("Fee: {} bps", fee_bps);
+    msg!("Verifier: {}", verifier_program);
     msg!("Tree height: {}", MERKLE_TREE_HEIGHT);
+    msg!("Root history: {}", root_history_size);
+    msg!("Nullifier shards: {}", nullifier_shards);
 
     Ok(())
 }
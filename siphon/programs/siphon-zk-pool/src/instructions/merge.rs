@@ -0,0 +1,309 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use crate::state::{MerkleTree, PoolConfig, CommitmentRecord, NullifierAccount, NullifierShard};
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::events::{EventHeader, MergeProcessed};
+use crate::notes::{emit_root_updated, insert_note, Note};
+use crate::nullifier;
+use crate::verifier::{self, asset_id};
+
+/// Merge request: spends up to MERGE_INPUTS notes of one asset and creates a
+/// single note holding their total, with zero public amount. Unused inputs are
+/// all-zero; input 0 must always be set.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct MergeInputs {
+    /// Layout version (must be MERGE_INPUTS_VERSION)
+    pub version: u8,
+    pub state_root: [u8; 32],
+    /// Generation of the tree that `state_root` belongs to
+    pub root_generation: u64,
+    /// Token mint, or Pubkey::default() for SOL
+    pub mint: Pubkey,
+    pub input_nullifier_hashes: [[u8; 32]; MERGE_INPUTS],
+    pub output_commitment: [u8; 32],
+    /// Encrypted note for the output
+    pub encrypted_output: Vec<u8>,
+}
+
+impl MergeInputs {
+    /// sha256(mint || len || encrypted_output) reduced to a field element; len is u32 BE
+    pub fn ext_data_hash(&self) -> [u8; 32] {
+        let mut hash = hashv(&[
+            self.mint.as_ref(),
+            &(self.encrypted_output.len() as u32).to_be_bytes(),
+            &self.encrypted_output,
+        ])
+        .to_bytes();
+        hash[0] = 0;
+        hash
+    }
+
+    /// Public inputs in circuit order:
+    /// (state_root, nullifier_hash_0..3, output_commitment, asset_id, ext_data_hash)
+    pub fn public_inputs(&self) -> [[u8; 32]; MERGE_INPUTS + 4] {
+        let mut public_inputs = [[0u8; 32]; MERGE_INPUTS + 4];
+        public_inputs[0] = self.state_root;
+        public_inputs[1..=MERGE_INPUTS].copy_from_slice(&self.input_nullifier_hashes);
+        public_inputs[MERGE_INPUTS + 1] = self.output_commitment;
+        public_inputs[MERGE_INPUTS + 2] = asset_id(&self.mint);
+        public_inputs[MERGE_INPUTS + 3] = self.ext_data_hash();
+        public_inputs
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(inputs: MergeInputs)]
+pub struct Merge<'info> {
+    /// Transaction submitter; pays rent for nullifiers and the commitment record
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+
+    #[account(
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    /// Tree whose root history contains `inputs.state_root` (any generation)
+    #[account(
+        seeds = [MERKLE_TREE_SEED, pool_config.key().as_ref(), &inputs.root_generation.to_le_bytes()],
+        bump = root_tree.load()?.bump,
+    )]
+    pub root_tree: AccountLoader<'info, MerkleTree>,
+
+    /// Active tree that receives the merged note
+    #[account(
+        mut,
+        seeds = [
+            MERKLE_TREE_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.current_generation.to_le_bytes(),
+        ],
+        bump = pool_config.tree_bump,
+    )]
+    pub merkle_tree: AccountLoader<'info, MerkleTree>,
+
+    /// Nullifier PDA for input 0 (per-nullifier mode)
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + NullifierAccount::INIT_SPACE,
+        seeds = [NULLIFIER_SEED, pool_config.key().as_ref(), inputs.input_nullifier_hashes[0].as_ref()],
+        bump
+    )]
+    pub nullifier_account_0: Option<Account<'info, NullifierAccount>>,
+
+    /// Nullifier PDA for input 1 (per-nullifier mode, omitted if input 1 is unused)
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + NullifierAccount::INIT_SPACE,
+        seeds = [NULLIFIER_SEED, pool_config.key().as_ref(), inputs.input_nullifier_hashes[1].as_ref()],
+        bump
+    )]
+    pub nullifier_account_1: Option<Account<'info, NullifierAccount>>,
+
+    /// Nullifier PDA for input 2 (per-nullifier mode, omitted if input 2 is unused)
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + NullifierAccount::INIT_SPACE,
+        seeds = [NULLIFIER_SEED, pool_config.key().as_ref(), inputs.input_nullifier_hashes[2].as_ref()],
+        bump
+    )]
+    pub nullifier_account_2: Option<Account<'info, NullifierAccount>>,
+
+    /// Nullifier PDA for input 3 (per-nullifier mode, omitted if input 3 is unused)
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + NullifierAccount::INIT_SPACE,
+        seeds = [NULLIFIER_SEED, pool_config.key().as_ref(), inputs.input_nullifier_hashes[3].as_ref()],
+        bump
+    )]
+    pub nullifier_account_3: Option<Account<'info, NullifierAccount>>,
+
+    /// Shard for input 0 (sharded mode)
+    #[account(
+        mut,
+        seeds = [
+            NULLIFIER_SHARD_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.nullifier_shard_index(&inputs.input_nullifier_hashes[0]).to_le_bytes(),
+        ],
+        bump = nullifier_shard_0.load()?.bump,
+    )]
+    pub nullifier_shard_0: Option<AccountLoader<'info, NullifierShard>>,
+
+    /// Shard for input 1 (sharded mode; omitted if an earlier input's shard is the same)
+    #[account(
+        mut,
+        seeds = [
+            NULLIFIER_SHARD_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.nullifier_shard_index(&inputs.input_nullifier_hashes[1]).to_le_bytes(),
+        ],
+        bump = nullifier_shard_1.load()?.bump,
+    )]
+    pub nullifier_shard_1: Option<AccountLoader<'info, NullifierShard>>,
+
+    /// Shard for input 2 (sharded mode; omitted if an earlier input's shard is the same)
+    #[account(
+        mut,
+        seeds = [
+            NULLIFIER_SHARD_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.nullifier_shard_index(&inputs.input_nullifier_hashes[2]).to_le_bytes(),
+        ],
+        bump = nullifier_shard_2.load()?.bump,
+    )]
+    pub nullifier_shard_2: Option<AccountLoader<'info, NullifierShard>>,
+
+    /// Shard for input 3 (sharded mode; omitted if an earlier input's shard is the same)
+    #[account(
+        mut,
+        seeds = [
+            NULLIFIER_SHARD_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.nullifier_shard_index(&inputs.input_nullifier_hashes[3]).to_le_bytes(),
+        ],
+        bump = nullifier_shard_3.load()?.bump,
+    )]
+    pub nullifier_shard_3: Option<AccountLoader<'info, NullifierShard>>,
+
+    /// Commitment record for the merged note
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + CommitmentRecord::INIT_SPACE,
+        seeds = [
+            COMMITMENT_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.current_generation.to_le_bytes(),
+            &merkle_tree.load()?.next_index.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub commitment_record: Account<'info, CommitmentRecord>,
+
+    /// Sunspot verifier program for the merge circuit
+    /// CHECK: Must match the merge verifier configured in pool config
+    #[account(
+        executable,
+        constraint = pool_config.merge_verifier_program != Pubkey::default() @ ZkPoolError::InvalidVerifierProgram,
+        constraint = verifier_program.key() == pool_config.merge_verifier_program @ ZkPoolError::InvalidVerifierProgram,
+    )]
+    pub verifier_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<Merge>, inputs: MergeInputs, proof: Vec<u8>) -> Result<()> {
+    let zero = [0u8; 32];
+    let nullifiers = inputs.input_nullifier_hashes;
+
+    require!(!ctx.accounts.pool_config.paused, ZkPoolError::ProtocolPaused);
+    require!(
+        inputs.version == MERGE_INPUTS_VERSION,
+        ZkPoolError::UnsupportedInputsVersion
+    );
+    require!(nullifiers[0] != zero, ZkPoolError::InvalidProof);
+    for (i, nullifier_hash) in nullifiers.iter().enumerate() {
+        require!(
+            *nullifier_hash == zero || !nullifiers[i + 1..].contains(nullifier_hash),
+            ZkPoolError::NullifierAlreadySpent
+        );
+    }
+    require!(inputs.output_commitment != zero, ZkPoolError::InvalidCommitment);
+
+    // Verify the state root is known in the referenced tree generation
+    {
+        let tree = ctx.accounts.root_tree.load()?;
+        require!(
+            tree.is_known_root(&inputs.state_root),
+            ZkPoolError::InvalidStateRoot
+        );
+    }
+
+    // Verify the merge proof (inputs and output balance, same asset)
+    verifier::verify_proof(
+        &ctx.accounts.verifier_program.to_account_info(),
+        &proof,
+        &inputs.public_inputs(),
+    )?;
+
+    // Mark the spent nullifiers; inputs in the same shard share one account
+    let pool_config = &ctx.accounts.pool_config;
+    let accounts = [
+        (ctx.accounts.nullifier_account_0.as_mut(), ctx.bumps.nullifier_account_0),
+        (ctx.accounts.nullifier_account_1.as_mut(), ctx.bumps.nullifier_account_1),
+        (ctx.accounts.nullifier_account_2.as_mut(), ctx.bumps.nullifier_account_2),
+        (ctx.accounts.nullifier_account_3.as_mut(), ctx.bumps.nullifier_account_3),
+    ];
+    let shards = [
+        ctx.accounts.nullifier_shard_0.as_ref(),
+        ctx.accounts.nullifier_shard_1.as_ref(),
+        ctx.accounts.nullifier_shard_2.as_ref(),
+        ctx.accounts.nullifier_shard_3.as_ref(),
+    ];
+    for (i, (nullifier_account, bump)) in accounts.into_iter().enumerate() {
+        let nullifier_hash = &nullifiers[i];
+        if *nullifier_hash == zero {
+            require!(
+                nullifier_account.is_none() && shards[i].is_none(),
+                ZkPoolError::InvalidNullifierAccount
+            );
+            continue;
+        }
+        let shard_index = pool_config.nullifier_shard_index(nullifier_hash);
+        let shard = shards[i].or_else(|| {
+            (0..i)
+                .filter(|&j| {
+                    nullifiers[j] != zero
+                        && pool_config.nullifier_shard_index(&nullifiers[j]) == shard_index
+                })
+                .find_map(|j| shards[j])
+        });
+        nullifier::mark_spent(
+            pool_config,
+            nullifier_account,
+            bump,
+            shard,
+            nullifier_hash,
+            &ctx.accounts.relayer,
+            &ctx.accounts.system_program,
+        )?;
+    }
+
+    // Insert the merged note and record it like a deposit
+    let mint = (inputs.mint != Pubkey::default()).then_some(inputs.mint);
+    let pool_id = ctx.accounts.pool_config.pool_id;
+    let mut tree = ctx.accounts.merkle_tree.load_mut()?;
+    let output_index = insert_note(
+        pool_id,
+        &mut tree,
+        &mut ctx.accounts.commitment_record,
+        ctx.accounts.relayer.key(),
+        ctx.bumps.commitment_record,
+        Note {
+            commitment: inputs.output_commitment,
+            encrypted_output: inputs.encrypted_output,
+            amount: None,
+            mint,
+        },
+    )?;
+    emit_root_updated(pool_id, &mut tree)?;
+
+    emit!(MergeProcessed {
+        header: EventHeader::tree(pool_id, &mut tree)?,
+        input_nullifier_hashes: nullifiers,
+        output_commitment: inputs.output_commitment,
+        output_index,
+        mint,
+    });
+
+    msg!("Notes merged: output={}", output_index);
+
+    Ok(())
+}
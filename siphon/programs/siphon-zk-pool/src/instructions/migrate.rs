@@ -0,0 +1,78 @@
+use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::events::{AccountMigrated, EventHeader};
+use crate::migration;
+
+#[derive(Accounts)]
+pub struct Migrate<'info> {
+    /// Pool admin; pays rent for the grown accounts
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    /// Pool config in any supported layout
+    /// CHECK: Older layouts may not load as `PoolConfig`; the handler checks the
+    /// discriminator and admin
+    #[account(mut, owner = crate::ID)]
+    pub pool_config: UncheckedAccount<'info>,
+
+    /// Optional tree of this pool (any generation) to upgrade as well
+    /// CHECK: The handler checks the discriminator and PDA
+    #[account(mut, owner = crate::ID)]
+    pub merkle_tree: Option<UncheckedAccount<'info>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Upgrade a pool's config (and optionally one tree) to the current account layouts.
+/// Accounts already at the current version are left unchanged.
+pub fn handler(ctx: Context<Migrate>) -> Result<()> {
+    let config_info = ctx.accounts.pool_config.to_account_info();
+    let (mut config, from_version) = migration::read_pool_config(&config_info)?;
+    require_keys_eq!(
+        config.admin,
+        ctx.accounts.admin.key(),
+        ZkPoolError::UnauthorizedAdmin
+    );
+    let config_sequence = config.config_sequence;
+
+    if from_version < POOL_CONFIG_VERSION {
+        emit!(AccountMigrated {
+            header: EventHeader::config(&mut config)?,
+            account: config_info.key(),
+            from_version,
+            to_version: POOL_CONFIG_VERSION,
+        });
+        msg!("Pool config upgraded from version {}", from_version);
+    }
+
+    if let Some(merkle_tree) = ctx.accounts.merkle_tree.as_ref() {
+        let tree_info = merkle_tree.to_account_info();
+        let from_version = migration::upgrade_merkle_tree(
+            &tree_info,
+            &config_info.key(),
+            &ctx.accounts.admin,
+            &ctx.accounts.system_program,
+        )?;
+        if from_version < MERKLE_TREE_VERSION {
+            emit!(AccountMigrated {
+                header: EventHeader::config(&mut config)?,
+                account: tree_info.key(),
+                from_version,
+                to_version: MERKLE_TREE_VERSION,
+            });
+        }
+    }
+
+    // Write back the upgraded layout and the config sequence the events consumed
+    if config.config_sequence != config_sequence {
+        migration::write_pool_config(
+            &config_info,
+            &config,
+            &ctx.accounts.admin,
+            &ctx.accounts.system_program,
+        )?;
+    }
+
+    Ok(())
+}
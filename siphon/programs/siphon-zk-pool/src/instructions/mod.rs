@@ -1,13 +1,63 @@
 pub mod initialize;
 pub mod deposit_sol;
 pub mod deposit_spl;
-pub mod update_root;
 pub mod withdraw_sol;
 pub mod withdraw_spl;
+pub mod set_paused;
+pub mod set_relayer;
+pub mod set_fee_config;
+pub mod set_verifier_program;
+pub mod propose_admin;
+pub mod accept_admin;
+pub mod register_mint;
+pub mod set_mint_enabled;
+pub mod set_denominations;
+pub mod rollover_tree;
+pub mod close_commitment_record;
+pub mod init_nullifier_shard;
+pub mod transact;
+pub mod set_transact_verifier_program;
+pub mod merge;
+pub mod set_merge_verifier_program;
+pub mod deposit_spl_sponsored;
+pub mod set_limits;
+pub mod set_guardian;
+pub mod deposit_wsol;
+pub mod register_swap_program;
+pub mod remove_swap_program;
+pub mod swap_shielded;
+pub mod update_mint_config;
+pub mod apply_verifier_program;
+pub mod migrate;
 
 pub use initialize::*;
 pub use deposit_sol::*;
 pub use deposit_spl::*;
-pub use update_root::*;
 pub use withdraw_sol::*;
 pub use withdraw_spl::*;
+pub use set_paused::*;
+pub use set_relayer::*;
+pub use set_fee_config::*;
+pub use set_verifier_program::*;
+pub use propose_admin::*;
+pub use accept_admin::*;
+pub use register_mint::*;
+pub use set_mint_enabled::*;
+pub use set_denominations::*;
+pub use rollover_tree::*;
+pub use close_commitment_record::*;
+pub use init_nullifier_shard::*;
+pub use transact::*;
+pub use set_transact_verifier_program::*;
+pub use merge::*;
+pub use set_merge_verifier_program::*;
+pub use deposit_spl_sponsored::*;
+pub use set_limits::*;
+pub use set_guardian::*;
+pub use deposit_wsol::*;
+pub use register_swap_program::*;
+pub use remove_swap_program::*;
+pub use swap_shielded::*;
+pub use update_mint_config::*;
+pub use apply_verifier_program::*;
+pub use migrate::*;
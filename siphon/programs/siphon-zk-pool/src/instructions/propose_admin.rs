@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+use crate::state::PoolConfig;
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::events::{EventHeader, AdminProposed};
+
+#[derive(Accounts)]
+pub struct ProposeAdmin<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
+        bump = pool_config.bump,
+        constraint = pool_config.admin == admin.key() @ ZkPoolError::UnauthorizedAdmin,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+}
+
+/// First step of the admin handover. Proposing `Pubkey::default()` cancels
+/// a pending proposal.
+pub fn handler(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.pool_config;
+    config.pending_admin = new_admin;
+
+    emit!(AdminProposed {
+        header: EventHeader::config(config)?,
+        admin: config.admin,
+        pending_admin: new_admin,
+    });
+
+    msg!("Admin proposed: {}", new_admin);
+
+    Ok(())
+}
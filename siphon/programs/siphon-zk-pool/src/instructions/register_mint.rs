@@ -0,0 +1,78 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::spl_token;
+use anchor_spl::token_interface::Mint;
+use crate::state::{MintConfig, PoolConfig, RateLimits};
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::events::{EventHeader, MintRegistered};
+use crate::tokens;
+
+#[derive(Accounts)]
+pub struct RegisterMint<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
+        bump = pool_config.bump,
+        constraint = pool_config.admin == admin.key() @ ZkPoolError::UnauthorizedAdmin,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    /// SPL Token or Token-2022 mint. wSOL is not registered: it shares SOL's notes
+    /// and vault (see `deposit_wsol` and `withdraw_sol`).
+    #[account(constraint = token_mint.key() != spl_token::native_mint::ID @ ZkPoolError::InvalidMint)]
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + MintConfig::INIT_SPACE,
+        seeds = [MINT_CONFIG_SEED, pool_config.key().as_ref(), token_mint.key().as_ref()],
+        bump
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(
+    ctx: Context<RegisterMint>,
+    min_deposit: u64,
+    max_deposit: u64,
+    fee_bps: u16,
+    min_fee: u64,
+) -> Result<()> {
+    require!(min_deposit > 0 && min_deposit <= max_deposit, ZkPoolError::InvalidAmount);
+    MintConfig::check_fee_config(min_deposit, fee_bps, min_fee)?;
+    tokens::check_mint_extensions(&ctx.accounts.token_mint)?;
+
+    let mint_key = ctx.accounts.token_mint.key();
+    let config = &mut ctx.accounts.mint_config;
+    config.mint = mint_key;
+    config.enabled = true;
+    config.limits = RateLimits {
+        min_deposit,
+        max_deposit,
+        ..RateLimits::unlimited()
+    };
+    config.decimals = ctx.accounts.token_mint.decimals;
+    config.fee_bps = fee_bps;
+    config.min_fee = min_fee;
+    config.bump = ctx.bumps.mint_config;
+    config.denominations = Vec::new();
+
+    emit!(MintRegistered {
+        header: EventHeader::config(&mut ctx.accounts.pool_config)?,
+        mint: mint_key,
+        min_deposit,
+        max_deposit,
+        fee_bps,
+        min_fee,
+    });
+
+    msg!("Mint registered: {}", mint_key);
+
+    Ok(())
+}
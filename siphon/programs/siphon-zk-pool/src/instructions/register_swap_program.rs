@@ -0,0 +1,53 @@
+use anchor_lang::prelude::*;
+use crate::state::{PoolConfig, SwapProgramConfig};
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::events::{EventHeader, SwapProgramUpdated};
+
+#[derive(Accounts)]
+pub struct RegisterSwapProgram<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
+        bump = pool_config.bump,
+        constraint = pool_config.admin == admin.key() @ ZkPoolError::UnauthorizedAdmin,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    /// AMM program to whitelist
+    /// CHECK: Only its address is stored
+    #[account(executable)]
+    pub swap_program: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + SwapProgramConfig::INIT_SPACE,
+        seeds = [SWAP_PROGRAM_SEED, pool_config.key().as_ref(), swap_program.key().as_ref()],
+        bump
+    )]
+    pub swap_program_config: Account<'info, SwapProgramConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Allow `swap_shielded` to route swaps through an AMM program
+pub fn handler(ctx: Context<RegisterSwapProgram>) -> Result<()> {
+    let program = ctx.accounts.swap_program.key();
+    let config = &mut ctx.accounts.swap_program_config;
+    config.program = program;
+    config.bump = ctx.bumps.swap_program_config;
+
+    emit!(SwapProgramUpdated {
+        header: EventHeader::config(&mut ctx.accounts.pool_config)?,
+        program,
+        enabled: true,
+    });
+
+    msg!("Swap program registered: {}", program);
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+use crate::state::{PoolConfig, SwapProgramConfig};
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::events::{EventHeader, SwapProgramUpdated};
+
+#[derive(Accounts)]
+pub struct RemoveSwapProgram<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
+        bump = pool_config.bump,
+        constraint = pool_config.admin == admin.key() @ ZkPoolError::UnauthorizedAdmin,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(
+        mut,
+        close = admin,
+        seeds = [SWAP_PROGRAM_SEED, pool_config.key().as_ref(), swap_program_config.program.as_ref()],
+        bump = swap_program_config.bump,
+    )]
+    pub swap_program_config: Account<'info, SwapProgramConfig>,
+}
+
+/// Remove an AMM program from the swap allow-list, refunding rent to the admin
+pub fn handler(ctx: Context<RemoveSwapProgram>) -> Result<()> {
+    let program = ctx.accounts.swap_program_config.program;
+
+    emit!(SwapProgramUpdated {
+        header: EventHeader::config(&mut ctx.accounts.pool_config)?,
+        program,
+        enabled: false,
+    });
+
+    msg!("Swap program removed: {}", program);
+
+    Ok(())
+}
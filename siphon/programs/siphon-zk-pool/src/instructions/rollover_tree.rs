@@ -0,0 +1,90 @@
+use anchor_lang::prelude::*;
+use crate::state::{MerkleTree, PoolConfig};
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::events::{EventHeader, RootUpdated, TreeRolledOver};
+
+#[derive(Accounts)]
+pub struct RolloverTree<'info> {
+    /// Anyone may open the next tree once the active one is full; pays rent
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    /// Active tree; must be full
+    #[account(
+        seeds = [
+            MERKLE_TREE_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.current_generation.to_le_bytes(),
+        ],
+        bump = pool_config.tree_bump,
+    )]
+    pub merkle_tree: AccountLoader<'info, MerkleTree>,
+
+    /// Next-generation tree
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + std::mem::size_of::<MerkleTree>(),
+        seeds = [
+            MERKLE_TREE_SEED,
+            pool_config.key().as_ref(),
+            &(pool_config.current_generation + 1).to_le_bytes(),
+        ],
+        bump
+    )]
+    pub new_tree: AccountLoader<'info, MerkleTree>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Open the next tree generation once the active tree is full. Older trees
+/// stay live: withdrawals may prove against any generation's root history.
+pub fn handler(ctx: Context<RolloverTree>) -> Result<()> {
+    let (old_generation, root_history_size) = {
+        let tree = ctx.accounts.merkle_tree.load()?;
+        require!(tree.is_full(), ZkPoolError::TreeNotFull);
+        (tree.generation, tree.root_history_size)
+    };
+    let new_generation = old_generation.checked_add(1).ok_or(ZkPoolError::Overflow)?;
+
+    let config = &mut ctx.accounts.pool_config;
+    config.current_generation = new_generation;
+    config.tree_bump = ctx.bumps.new_tree;
+
+    let mut tree = ctx.accounts.new_tree.load_init()?;
+    tree.authority = config.relayer;
+    tree.generation = new_generation;
+    tree.next_index = 0;
+    tree.root_history_index = 0;
+    tree.height = MERKLE_TREE_HEIGHT;
+    tree.version = MERKLE_TREE_VERSION;
+    tree.bump = ctx.bumps.new_tree;
+    tree.root_history_size = root_history_size;
+    tree.init_zeros()?;
+
+    emit!(TreeRolledOver {
+        header: EventHeader::tree(config.pool_id, &mut tree)?,
+        old_generation,
+        new_generation,
+        new_tree: ctx.accounts.new_tree.key(),
+    });
+
+    emit!(RootUpdated {
+        header: EventHeader::tree(config.pool_id, &mut tree)?,
+        new_root: tree.current_root,
+        root_index: tree.root_history_index,
+        leaf_count: tree.next_index,
+    });
+
+    msg!("Merkle tree rolled over to generation {}", new_generation);
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use anchor_lang::prelude::*;
+use crate::state::{check_denominations, MintConfig, PoolConfig};
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::events::{EventHeader, DenominationsUpdated};
+
+#[derive(Accounts)]
+pub struct SetDenominations<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
+        bump = pool_config.bump,
+        constraint = pool_config.admin == admin.key() @ ZkPoolError::UnauthorizedAdmin,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    /// Mint to update; omit to update the SOL denominations on `pool_config`
+    #[account(
+        mut,
+        seeds = [MINT_CONFIG_SEED, pool_config.key().as_ref(), mint_config.mint.as_ref()],
+        bump = mint_config.bump,
+    )]
+    pub mint_config: Option<Account<'info, MintConfig>>,
+}
+
+/// Restrict an asset's deposits to fixed amounts in its base units. An empty
+/// list switches the asset back to arbitrary amounts. Withdrawals are never
+/// checked, so existing notes stay spendable whatever the list becomes.
+pub fn handler(ctx: Context<SetDenominations>, denominations: Vec<u64>) -> Result<()> {
+    check_denominations(&denominations)?;
+
+    let mint = match ctx.accounts.mint_config.as_mut() {
+        Some(config) => {
+            config.denominations = denominations.clone();
+            Some(config.mint)
+        }
+        None => {
+            ctx.accounts.pool_config.sol_denominations = denominations.clone();
+            None
+        }
+    };
+
+    emit!(DenominationsUpdated {
+        header: EventHeader::config(&mut ctx.accounts.pool_config)?,
+        mint,
+        denominations,
+    });
+
+    msg!("Denominations updated for {:?}", mint);
+
+    Ok(())
+}
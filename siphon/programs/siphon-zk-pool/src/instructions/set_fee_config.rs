@@ -0,0 +1,36 @@
+use anchor_lang::prelude::*;
+use crate::state::PoolConfig;
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::events::{EventHeader, FeeConfigUpdated};
+
+#[derive(Accounts)]
+pub struct SetFeeConfig<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
+        bump = pool_config.bump,
+        constraint = pool_config.admin == admin.key() @ ZkPoolError::UnauthorizedAdmin,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+}
+
+pub fn handler(ctx: Context<SetFeeConfig>, fee_bps: u16, fee_recipient: Pubkey) -> Result<()> {
+    require!(fee_bps <= MAX_FEE_BPS, ZkPoolError::InvalidFeeConfig);
+
+    let config = &mut ctx.accounts.pool_config;
+    config.fee_bps = fee_bps;
+    config.fee_recipient = fee_recipient;
+
+    emit!(FeeConfigUpdated {
+        header: EventHeader::config(config)?,
+        fee_bps,
+        fee_recipient,
+    });
+
+    msg!("Fee config updated: {} bps, recipient={}", fee_bps, fee_recipient);
+
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+use crate::state::PoolConfig;
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::events::{EventHeader, GuardianUpdated};
+
+#[derive(Accounts)]
+pub struct SetGuardian<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
+        bump = pool_config.bump,
+        constraint = pool_config.admin == admin.key() @ ZkPoolError::UnauthorizedAdmin,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+}
+
+/// Set the guardian that may pause the pool. `Pubkey::default()` removes it.
+pub fn handler(ctx: Context<SetGuardian>, new_guardian: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.pool_config;
+    let old_guardian = config.guardian;
+    config.guardian = new_guardian;
+
+    emit!(GuardianUpdated {
+        header: EventHeader::config(config)?,
+        old_guardian,
+        new_guardian,
+    });
+
+    msg!("Guardian updated: {}", new_guardian);
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+use crate::state::{MintConfig, PoolConfig};
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::events::{EventHeader, LimitsUpdated};
+
+#[derive(Accounts)]
+pub struct SetLimits<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
+        bump = pool_config.bump,
+        constraint = pool_config.admin == admin.key() @ ZkPoolError::UnauthorizedAdmin,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    /// Mint to update; omit to update the SOL limits on `pool_config`
+    #[account(
+        mut,
+        seeds = [MINT_CONFIG_SEED, pool_config.key().as_ref(), mint_config.mint.as_ref()],
+        bump = mint_config.bump,
+    )]
+    pub mint_config: Option<Account<'info, MintConfig>>,
+}
+
+/// Update an asset's amount limits. The current epoch's withdrawn total is kept,
+/// so lowering the cap takes effect immediately. Rejected while paused; withdrawals
+/// through the escape hatch ignore the limits either way.
+pub fn handler(
+    ctx: Context<SetLimits>,
+    min_deposit: u64,
+    max_deposit: u64,
+    max_withdrawal: u64,
+    epoch_withdrawal_cap: u64,
+) -> Result<()> {
+    require!(!ctx.accounts.pool_config.paused, ZkPoolError::ConfigFrozenWhilePaused);
+
+    let (mint, limits) = match ctx.accounts.mint_config.as_mut() {
+        Some(config) => {
+            // Same bounds as `register_mint`: the flat fee must stay within
+            // MAX_FEE_BPS of the smallest deposit
+            require!(min_deposit > 0, ZkPoolError::InvalidAmount);
+            MintConfig::check_fee_config(min_deposit, config.fee_bps, config.min_fee)?;
+            (Some(config.mint), &mut config.limits)
+        }
+        None => (None, &mut ctx.accounts.pool_config.sol_limits),
+    };
+    limits.update(min_deposit, max_deposit, max_withdrawal, epoch_withdrawal_cap)?;
+
+    emit!(LimitsUpdated {
+        header: EventHeader::config(&mut ctx.accounts.pool_config)?,
+        mint,
+        min_deposit,
+        max_deposit,
+        max_withdrawal,
+        epoch_withdrawal_cap,
+    });
+
+    msg!("Limits updated for {:?}", mint);
+
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+use crate::state::PoolConfig;
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::events::{EventHeader, MergeVerifierProgramUpdated};
+
+#[derive(Accounts)]
+pub struct SetMergeVerifierProgram<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
+        bump = pool_config.bump,
+        constraint = pool_config.admin == admin.key() @ ZkPoolError::UnauthorizedAdmin,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+}
+
+/// Set the verifier for the merge circuit. `Pubkey::default()` disables `merge`.
+pub fn handler(ctx: Context<SetMergeVerifierProgram>, verifier_program: Pubkey) -> Result<()> {
+    require!(!ctx.accounts.pool_config.paused, ZkPoolError::ConfigFrozenWhilePaused);
+
+    let config = &mut ctx.accounts.pool_config;
+    let old_verifier = config.merge_verifier_program;
+    config.merge_verifier_program = verifier_program;
+
+    emit!(MergeVerifierProgramUpdated {
+        header: EventHeader::config(config)?,
+        old_verifier,
+        new_verifier: verifier_program,
+    });
+
+    msg!("Merge verifier program updated: {}", verifier_program);
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+use crate::state::{MintConfig, PoolConfig};
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::events::{EventHeader, MintEnabledUpdated};
+
+#[derive(Accounts)]
+pub struct SetMintEnabled<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
+        bump = pool_config.bump,
+        constraint = pool_config.admin == admin.key() @ ZkPoolError::UnauthorizedAdmin,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(
+        mut,
+        seeds = [MINT_CONFIG_SEED, pool_config.key().as_ref(), mint_config.mint.as_ref()],
+        bump = mint_config.bump,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+}
+
+/// Enable or disable deposits for a mint. Withdrawals of existing notes stay open.
+pub fn handler(ctx: Context<SetMintEnabled>, enabled: bool) -> Result<()> {
+    let config = &mut ctx.accounts.mint_config;
+    config.enabled = enabled;
+
+    emit!(MintEnabledUpdated {
+        header: EventHeader::config(&mut ctx.accounts.pool_config)?,
+        mint: config.mint,
+        enabled,
+    });
+
+    msg!("Mint {} enabled: {}", config.mint, enabled);
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+use crate::state::PoolConfig;
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::events::{EventHeader, PauseUpdated};
+
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    /// Admin, or the guardian (which may only pause)
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
+        bump = pool_config.bump,
+        constraint = pool_config.admin == authority.key()
+            || pool_config.guardian == authority.key() @ ZkPoolError::UnauthorizedAdmin,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+}
+
+pub fn handler(ctx: Context<SetPaused>, paused: bool) -> Result<()> {
+    let authority = ctx.accounts.authority.key();
+    let config = &mut ctx.accounts.pool_config;
+    require!(
+        paused || config.admin == authority,
+        ZkPoolError::UnauthorizedAdmin
+    );
+
+    config.set_paused(paused, Clock::get()?.unix_timestamp);
+
+    emit!(PauseUpdated {
+        header: EventHeader::config(config)?,
+        paused,
+        authority,
+    });
+
+    msg!("Pool paused: {}", paused);
+
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+use crate::state::PoolConfig;
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::events::{EventHeader, RelayerUpdated};
+
+#[derive(Accounts)]
+pub struct SetRelayer<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
+        bump = pool_config.bump,
+        constraint = pool_config.admin == admin.key() @ ZkPoolError::UnauthorizedAdmin,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+}
+
+pub fn handler(ctx: Context<SetRelayer>, new_relayer: Pubkey) -> Result<()> {
+    let config = &mut ctx.accounts.pool_config;
+    let old_relayer = config.relayer;
+    config.relayer = new_relayer;
+
+    emit!(RelayerUpdated {
+        header: EventHeader::config(config)?,
+        old_relayer,
+        new_relayer,
+    });
+
+    msg!("Relayer updated: {}", new_relayer);
+
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+use crate::state::PoolConfig;
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::events::{EventHeader, TransactVerifierProgramUpdated};
+
+#[derive(Accounts)]
+pub struct SetTransactVerifierProgram<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
+        bump = pool_config.bump,
+        constraint = pool_config.admin == admin.key() @ ZkPoolError::UnauthorizedAdmin,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+}
+
+/// Set the verifier for the join-split circuit. `Pubkey::default()` disables `transact`.
+pub fn handler(ctx: Context<SetTransactVerifierProgram>, verifier_program: Pubkey) -> Result<()> {
+    require!(!ctx.accounts.pool_config.paused, ZkPoolError::ConfigFrozenWhilePaused);
+
+    let config = &mut ctx.accounts.pool_config;
+    let old_verifier = config.transact_verifier_program;
+    config.transact_verifier_program = verifier_program;
+
+    emit!(TransactVerifierProgramUpdated {
+        header: EventHeader::config(config)?,
+        old_verifier,
+        new_verifier: verifier_program,
+    });
+
+    msg!("Transact verifier program updated: {}", verifier_program);
+
+    Ok(())
+}
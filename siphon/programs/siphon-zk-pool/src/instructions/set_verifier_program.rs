@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+use crate::state::PoolConfig;
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::events::{EventHeader, VerifierProgramProposed};
+
+#[derive(Accounts)]
+pub struct SetVerifierProgram<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
+        bump = pool_config.bump,
+        constraint = pool_config.admin == admin.key() @ ZkPoolError::UnauthorizedAdmin,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+}
+
+/// Propose a new withdrawal verifier. It is installed by `apply_verifier_program`
+/// after ESCAPE_HATCH_DELAY_SECONDS of the pool staying live, so a swap can neither
+/// surprise users nor shut the escape hatch.
+pub fn handler(ctx: Context<SetVerifierProgram>, verifier_program: Pubkey) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    let config = &mut ctx.accounts.pool_config;
+    config.propose_verifier(verifier_program, now)?;
+
+    emit!(VerifierProgramProposed {
+        header: EventHeader::config(config)?,
+        pending_verifier: verifier_program,
+        effective_at: now.saturating_add(ESCAPE_HATCH_DELAY_SECONDS),
+    });
+
+    msg!("Verifier program proposed: {}", verifier_program);
+
+    Ok(())
+}
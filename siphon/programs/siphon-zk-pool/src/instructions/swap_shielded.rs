@@ -0,0 +1,494 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use crate::state::{
+    MerkleTree, PoolConfig, CommitmentRecord, MintConfig, NullifierAccount, NullifierShard,
+    SwapProgramConfig,
+};
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::events::{EventHeader, SwapProcessed};
+use crate::notes::{emit_root_updated, insert_note, Note};
+use crate::nullifier;
+use crate::poseidon;
+use crate::tokens;
+use crate::verifier::{self, asset_id, u64_to_field};
+
+/// Shielded swap request. Spends a note of `input_mint` with the withdrawal circuit,
+/// swaps `amount_in` through a whitelisted AMM and deposits the proceeds as a new
+/// `output_mint` note. `ext_data_hash()` binds the whole route into the proof.
+/// Both legs are registered SPL mints: SOL notes are vault lamports, and wSOL has no
+/// `MintConfig`, so SOL cannot be swapped in either direction.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct SwapInputs {
+    /// Layout version (must be SWAP_INPUTS_VERSION)
+    pub version: u8,
+    pub nullifier_hash: [u8; 32],
+    pub state_root: [u8; 32],
+    /// Generation of the tree that `state_root` belongs to
+    pub root_generation: u64,
+    /// Change note of the input asset (all-zero when the note is fully spent)
+    pub new_commitment: [u8; 32],
+    pub input_mint: Pubkey,
+    /// Amount handed to the AMM
+    pub amount_in: u64,
+    /// Relayer fee, paid in the input mint
+    pub fee: u64,
+    /// Fee payee (token account owner)
+    pub fee_recipient: Pubkey,
+    pub output_mint: Pubkey,
+    /// Minimum amount the pool must receive from the AMM
+    pub min_amount_out: u64,
+    /// Precommitment of the output note; its value is the amount actually received
+    pub output_precommitment: [u8; 32],
+    pub swap_program: Pubkey,
+    /// Instruction data for the AMM
+    pub swap_data: Vec<u8>,
+    /// Encrypted notes: [output note, change note] (empty when unused)
+    pub encrypted_outputs: [Vec<u8>; 2],
+}
+
+impl SwapInputs {
+    /// sha256("swap" || input_mint || amount_in || fee || fee_recipient || output_mint ||
+    /// min_amount_out || output_precommitment || swap_program || len || swap_data ||
+    /// len || encrypted_output_0 || len || encrypted_output_1) reduced to a field
+    /// element; integers are BE, lengths u32 BE
+    pub fn ext_data_hash(&self) -> [u8; 32] {
+        let [out_0, out_1] = &self.encrypted_outputs;
+        let mut hash = hashv(&[
+            b"swap",
+            self.input_mint.as_ref(),
+            &self.amount_in.to_be_bytes(),
+            &self.fee.to_be_bytes(),
+            self.fee_recipient.as_ref(),
+            self.output_mint.as_ref(),
+            &self.min_amount_out.to_be_bytes(),
+            &self.output_precommitment,
+            self.swap_program.as_ref(),
+            &(self.swap_data.len() as u32).to_be_bytes(),
+            &self.swap_data,
+            &(out_0.len() as u32).to_be_bytes(),
+            out_0,
+            &(out_1.len() as u32).to_be_bytes(),
+            out_1,
+        ])
+        .to_bytes();
+        hash[0] = 0;
+        hash
+    }
+
+    /// Public inputs of the withdrawal circuit:
+    /// (withdrawn_value, state_root, new_commitment, nullifier_hash, asset_id, ext_data_hash)
+    pub fn public_inputs(&self, withdrawn_value: u64) -> [[u8; 32]; 6] {
+        [
+            u64_to_field(withdrawn_value),
+            self.state_root,
+            self.new_commitment,
+            self.nullifier_hash,
+            asset_id(&self.input_mint),
+            self.ext_data_hash(),
+        ]
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(inputs: SwapInputs)]
+pub struct SwapShielded<'info> {
+    /// Transaction submitter; pays rent for the nullifier and commitment records
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+
+    #[account(
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Box<Account<'info, PoolConfig>>,
+
+    /// Tree whose root history contains `inputs.state_root` (any generation)
+    #[account(
+        seeds = [MERKLE_TREE_SEED, pool_config.key().as_ref(), &inputs.root_generation.to_le_bytes()],
+        bump = root_tree.load()?.bump,
+    )]
+    pub root_tree: AccountLoader<'info, MerkleTree>,
+
+    /// Active tree that receives the output and change commitments
+    #[account(
+        mut,
+        seeds = [
+            MERKLE_TREE_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.current_generation.to_le_bytes(),
+        ],
+        bump = pool_config.tree_bump,
+    )]
+    pub merkle_tree: AccountLoader<'info, MerkleTree>,
+
+    /// Pool vault; signs the AMM instruction as owner of the pool token accounts
+    /// CHECK: PDA used as token authority
+    #[account(
+        seeds = [POOL_VAULT_SEED, pool_config.key().as_ref()],
+        bump = pool_config.vault_bump,
+    )]
+    pub pool_vault: SystemAccount<'info>,
+
+    #[account(address = inputs.input_mint @ ZkPoolError::InvalidMint)]
+    pub input_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Input mint config; fees and withdrawal limits apply to the spent amount
+    #[account(
+        mut,
+        seeds = [MINT_CONFIG_SEED, pool_config.key().as_ref(), input_mint.key().as_ref()],
+        bump = input_mint_config.bump,
+    )]
+    pub input_mint_config: Box<Account<'info, MintConfig>>,
+
+    #[account(
+        address = inputs.output_mint @ ZkPoolError::InvalidMint,
+        constraint = output_mint.key() != input_mint.key() @ ZkPoolError::InvalidMint,
+    )]
+    pub output_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Output mint config; the output note is a deposit and must be enabled
+    #[account(
+        seeds = [MINT_CONFIG_SEED, pool_config.key().as_ref(), output_mint.key().as_ref()],
+        bump = output_mint_config.bump,
+        constraint = output_mint_config.enabled @ ZkPoolError::MintNotAllowed,
+    )]
+    pub output_mint_config: Box<Account<'info, MintConfig>>,
+
+    /// Pool's token account for the input mint (AMM source)
+    #[account(
+        mut,
+        constraint = pool_input_token_account.mint == input_mint.key(),
+        constraint = pool_input_token_account.owner == pool_vault.key(),
+    )]
+    pub pool_input_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Pool's token account for the output mint (AMM destination)
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        associated_token::mint = output_mint,
+        associated_token::authority = pool_vault,
+        associated_token::token_program = token_program,
+    )]
+    pub pool_output_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Fee recipient's token account for the input mint
+    #[account(
+        mut,
+        constraint = fee_recipient_token_account.mint == input_mint.key(),
+        constraint = fee_recipient_token_account.owner == inputs.fee_recipient @ ZkPoolError::InvalidFeeConfig,
+    )]
+    pub fee_recipient_token_account: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// AMM program; the remaining accounts are passed to it as-is
+    /// CHECK: Must be `inputs.swap_program` and whitelisted
+    #[account(
+        executable,
+        address = inputs.swap_program @ ZkPoolError::InvalidSwapAccounts,
+    )]
+    pub swap_program: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [SWAP_PROGRAM_SEED, pool_config.key().as_ref(), swap_program.key().as_ref()],
+        bump = swap_program_config.bump,
+    )]
+    pub swap_program_config: Box<Account<'info, SwapProgramConfig>>,
+
+    /// Nullifier PDA — per-nullifier mode (`pool_config.nullifier_shards == 0`)
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + NullifierAccount::INIT_SPACE,
+        seeds = [NULLIFIER_SEED, pool_config.key().as_ref(), inputs.nullifier_hash.as_ref()],
+        bump
+    )]
+    pub nullifier_account: Option<Box<Account<'info, NullifierAccount>>>,
+
+    /// Nullifier shard — sharded mode; must be the shard for `inputs.nullifier_hash`
+    #[account(
+        mut,
+        seeds = [
+            NULLIFIER_SHARD_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.nullifier_shard_index(&inputs.nullifier_hash).to_le_bytes(),
+        ],
+        bump = nullifier_shard.load()?.bump,
+    )]
+    pub nullifier_shard: Option<AccountLoader<'info, NullifierShard>>,
+
+    /// Commitment record for the output note (the active tree's next leaf)
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + CommitmentRecord::INIT_SPACE,
+        seeds = [
+            COMMITMENT_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.current_generation.to_le_bytes(),
+            &merkle_tree.load()?.next_index.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub output_record: Box<Account<'info, CommitmentRecord>>,
+
+    /// Commitment record for the change note (required iff `inputs.new_commitment` is set)
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + CommitmentRecord::INIT_SPACE,
+        seeds = [
+            COMMITMENT_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.current_generation.to_le_bytes(),
+            &(merkle_tree.load()?.next_index + 1).to_le_bytes(),
+        ],
+        bump
+    )]
+    pub change_record: Option<Box<Account<'info, CommitmentRecord>>>,
+
+    /// Sunspot verifier program for the withdrawal circuit
+    /// CHECK: Must match the verifier configured in pool config
+    #[account(
+        executable,
+        constraint = pool_config.verifier_program != Pubkey::default() @ ZkPoolError::InvalidVerifierProgram,
+        constraint = verifier_program.key() == pool_config.verifier_program @ ZkPoolError::InvalidVerifierProgram,
+    )]
+    pub verifier_program: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SwapShielded<'info>>,
+    inputs: SwapInputs,
+    proof: Vec<u8>,
+) -> Result<()> {
+    let SwapInputs { amount_in, fee, min_amount_out, .. } = inputs;
+    let zero = [0u8; 32];
+
+    require!(!ctx.accounts.pool_config.paused, ZkPoolError::ProtocolPaused);
+    require!(
+        inputs.version == SWAP_INPUTS_VERSION,
+        ZkPoolError::UnsupportedInputsVersion
+    );
+    require!(amount_in > 0, ZkPoolError::InvalidAmount);
+    // Swap proceeds never match a fixed denomination of the output asset
+    require!(
+        !ctx.accounts.output_mint_config.is_denominated(),
+        ZkPoolError::InvalidDenomination
+    );
+    require!(inputs.output_precommitment != zero, ZkPoolError::InvalidCommitment);
+    require!(
+        (inputs.new_commitment != zero) == ctx.accounts.change_record.is_some(),
+        ZkPoolError::InvalidCommitment
+    );
+    ctx.accounts.input_mint_config.check_fee(amount_in, fee)?;
+
+    ctx.accounts
+        .pool_config
+        .check_fee_recipient(&ctx.accounts.relayer.key(), &inputs.fee_recipient)?;
+
+    // Verify the state root is known
+    {
+        let tree = ctx.accounts.root_tree.load()?;
+        require!(
+            tree.is_known_root(&inputs.state_root),
+            ZkPoolError::InvalidStateRoot
+        );
+    }
+
+    // Verify the withdrawal proof; the note's withdrawn value covers amount_in + fee.
+    // Funds leave the input asset's custody, so its withdrawal limits apply.
+    let total_out = amount_in.checked_add(fee).ok_or(ZkPoolError::Overflow)?;
+    let epoch = Clock::get()?.epoch;
+    ctx.accounts
+        .input_mint_config
+        .limits
+        .record_withdrawal(total_out, epoch)?;
+    verifier::verify_proof(
+        &ctx.accounts.verifier_program.to_account_info(),
+        &proof,
+        &inputs.public_inputs(total_out),
+    )?;
+
+    // Mark nullifier as spent in the store this pool uses
+    nullifier::mark_spent(
+        &ctx.accounts.pool_config,
+        ctx.accounts.nullifier_account.as_deref_mut(),
+        ctx.bumps.nullifier_account,
+        ctx.accounts.nullifier_shard.as_ref(),
+        &inputs.nullifier_hash,
+        &ctx.accounts.relayer,
+        &ctx.accounts.system_program,
+    )?;
+
+    let pool_key = ctx.accounts.pool_config.key();
+    let vault_key = ctx.accounts.pool_vault.key();
+    let vault_bump = ctx.accounts.pool_config.vault_bump;
+    let vault_seeds: &[&[&[u8]]] = &[&[POOL_VAULT_SEED, pool_key.as_ref(), &[vault_bump]]];
+
+    // Pay the relayer fee in the input mint
+    if fee > 0 {
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.pool_input_token_account.to_account_info(),
+                    mint: ctx.accounts.input_mint.to_account_info(),
+                    to: ctx.accounts.fee_recipient_token_account.to_account_info(),
+                    authority: ctx.accounts.pool_vault.to_account_info(),
+                },
+                vault_seeds,
+            ),
+            fee,
+            ctx.accounts.input_mint.decimals,
+        )?;
+        ctx.accounts.pool_input_token_account.reload()?;
+    }
+
+    // The vault signs for every pool token account, so the AMM may only touch the
+    // input and output accounts among them. The vault itself must stay read-only:
+    // as a writable signer it could be drained, allocated or reassigned.
+    let input_key = ctx.accounts.pool_input_token_account.key();
+    let output_key = ctx.accounts.pool_output_token_account.key();
+    for account in ctx.remaining_accounts.iter().filter(|account| account.is_writable) {
+        require_keys_neq!(account.key(), vault_key, ZkPoolError::InvalidSwapAccounts);
+        if account.key() != input_key && account.key() != output_key {
+            require!(
+                !tokens::is_token_account_of(account, &vault_key),
+                ZkPoolError::InvalidSwapAccounts
+            );
+        }
+    }
+
+    let input_before = ctx.accounts.pool_input_token_account.amount;
+    let output_before = ctx.accounts.pool_output_token_account.amount;
+    let vault_lamports = ctx.accounts.pool_vault.lamports();
+
+    // Swap through the AMM with the pool vault as signer
+    let swap_ix = Instruction {
+        program_id: ctx.accounts.swap_program.key(),
+        accounts: ctx
+            .remaining_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: account.key(),
+                is_signer: account.is_signer || account.key() == vault_key,
+                is_writable: account.is_writable,
+            })
+            .collect(),
+        data: inputs.swap_data.clone(),
+    };
+    let mut swap_accounts = ctx.remaining_accounts.to_vec();
+    swap_accounts.push(ctx.accounts.swap_program.to_account_info());
+    invoke_signed(&swap_ix, &swap_accounts, vault_seeds)?;
+
+    // Exactly `amount_in` must have left the pool, no SOL may have moved, the vault
+    // must still be a plain system account solely controlling both token accounts,
+    // and at least `min_amount_out` must have arrived
+    ctx.accounts.pool_input_token_account.reload()?;
+    ctx.accounts.pool_output_token_account.reload()?;
+    require!(
+        tokens::has_sole_authority(&ctx.accounts.pool_input_token_account, &vault_key)
+            && tokens::has_sole_authority(&ctx.accounts.pool_output_token_account, &vault_key),
+        ZkPoolError::InvalidSwapAccounts
+    );
+    require!(
+        input_before.checked_sub(ctx.accounts.pool_input_token_account.amount) == Some(amount_in),
+        ZkPoolError::InvalidSwapAccounts
+    );
+    let vault_info = ctx.accounts.pool_vault.to_account_info();
+    require!(
+        vault_info.lamports() == vault_lamports
+            && vault_info.owner == &system_program::ID
+            && vault_info.data_is_empty(),
+        ZkPoolError::InvalidSwapAccounts
+    );
+    let amount_out = ctx
+        .accounts
+        .pool_output_token_account
+        .amount
+        .checked_sub(output_before)
+        .ok_or(ZkPoolError::InvalidSwapAccounts)?;
+    require!(
+        amount_out > 0 && amount_out >= min_amount_out,
+        ZkPoolError::SlippageExceeded
+    );
+    ctx.accounts.output_mint_config.limits.check_deposit(amount_out)?;
+
+    // Deposit the proceeds as a note bound to the received amount and output mint
+    let output_mint = ctx.accounts.output_mint.key();
+    let output_commitment = poseidon::commitment(
+        amount_out,
+        &asset_id(&output_mint),
+        &inputs.output_precommitment,
+    )?;
+
+    let relayer = ctx.accounts.relayer.key();
+    let pool_id = ctx.accounts.pool_config.pool_id;
+    let mut tree = ctx.accounts.merkle_tree.load_mut()?;
+    let output_index = insert_note(
+        pool_id,
+        &mut tree,
+        &mut ctx.accounts.output_record,
+        relayer,
+        ctx.bumps.output_record,
+        Note {
+            commitment: output_commitment,
+            encrypted_output: inputs.encrypted_outputs[0].clone(),
+            amount: Some(amount_out),
+            mint: Some(output_mint),
+        },
+    )?;
+
+    // Change note of the input asset
+    let mut change_index: Option<u64> = None;
+    if let Some(record) = ctx.accounts.change_record.as_mut() {
+        change_index = Some(insert_note(
+            pool_id,
+            &mut tree,
+            record,
+            relayer,
+            ctx.bumps.change_record.ok_or(ZkPoolError::InvalidCommitment)?,
+            Note {
+                commitment: inputs.new_commitment,
+                encrypted_output: inputs.encrypted_outputs[1].clone(),
+                amount: None, // Change amount is hidden
+                mint: Some(inputs.input_mint),
+            },
+        )?);
+    }
+
+    emit_root_updated(pool_id, &mut tree)?;
+
+    emit!(SwapProcessed {
+        header: EventHeader::tree(ctx.accounts.pool_config.pool_id, &mut tree)?,
+        nullifier_hash: inputs.nullifier_hash,
+        swap_program: inputs.swap_program,
+        input_mint: inputs.input_mint,
+        amount_in,
+        fee,
+        output_mint,
+        amount_out,
+        output_index,
+        change_index,
+    });
+
+    msg!(
+        "Shielded swap: in={} {}, out={} {}",
+        amount_in,
+        inputs.input_mint,
+        amount_out,
+        output_mint
+    );
+
+    Ok(())
+}
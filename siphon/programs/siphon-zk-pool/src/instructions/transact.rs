@@ -0,0 +1,291 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+use crate::state::{MerkleTree, PoolConfig, CommitmentRecord, NullifierAccount, NullifierShard};
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::events::{EventHeader, TransactProcessed};
+use crate::notes::{emit_root_updated, insert_note, Note};
+use crate::nullifier;
+use crate::verifier::{self, asset_id};
+
+/// Shielded transfer request: spends up to two notes and creates up to two
+/// notes of the same asset with zero public amount. Unused inputs and outputs
+/// are all-zero; output 0 must always be set.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct TransactInputs {
+    /// Layout version (must be TRANSACT_INPUTS_VERSION)
+    pub version: u8,
+    pub state_root: [u8; 32],
+    /// Generation of the tree that `state_root` belongs to
+    pub root_generation: u64,
+    /// Token mint, or Pubkey::default() for SOL
+    pub mint: Pubkey,
+    pub input_nullifier_hashes: [[u8; 32]; 2],
+    pub output_commitments: [[u8; 32]; 2],
+    /// Encrypted notes for the outputs (empty for unused outputs)
+    pub encrypted_outputs: [Vec<u8>; 2],
+}
+
+impl TransactInputs {
+    /// sha256(mint || len || encrypted_output_0 || len || encrypted_output_1)
+    /// reduced to a field element; lengths are u32 BE
+    pub fn ext_data_hash(&self) -> [u8; 32] {
+        let [out_0, out_1] = &self.encrypted_outputs;
+        let mut hash = hashv(&[
+            self.mint.as_ref(),
+            &(out_0.len() as u32).to_be_bytes(),
+            out_0,
+            &(out_1.len() as u32).to_be_bytes(),
+            out_1,
+        ])
+        .to_bytes();
+        hash[0] = 0;
+        hash
+    }
+
+    /// Public inputs in circuit order:
+    /// (state_root, nullifier_hash_0, nullifier_hash_1, output_commitment_0,
+    ///  output_commitment_1, asset_id, ext_data_hash)
+    pub fn public_inputs(&self) -> [[u8; 32]; 7] {
+        [
+            self.state_root,
+            self.input_nullifier_hashes[0],
+            self.input_nullifier_hashes[1],
+            self.output_commitments[0],
+            self.output_commitments[1],
+            asset_id(&self.mint),
+            self.ext_data_hash(),
+        ]
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(inputs: TransactInputs)]
+pub struct Transact<'info> {
+    /// Transaction submitter; pays rent for nullifiers and commitment records
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+
+    #[account(
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
+        bump = pool_config.bump,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    /// Tree whose root history contains `inputs.state_root` (any generation)
+    #[account(
+        seeds = [MERKLE_TREE_SEED, pool_config.key().as_ref(), &inputs.root_generation.to_le_bytes()],
+        bump = root_tree.load()?.bump,
+    )]
+    pub root_tree: AccountLoader<'info, MerkleTree>,
+
+    /// Active tree that receives the output commitments
+    #[account(
+        mut,
+        seeds = [
+            MERKLE_TREE_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.current_generation.to_le_bytes(),
+        ],
+        bump = pool_config.tree_bump,
+    )]
+    pub merkle_tree: AccountLoader<'info, MerkleTree>,
+
+    /// Nullifier PDA for input 0 (per-nullifier mode)
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + NullifierAccount::INIT_SPACE,
+        seeds = [NULLIFIER_SEED, pool_config.key().as_ref(), inputs.input_nullifier_hashes[0].as_ref()],
+        bump
+    )]
+    pub nullifier_account_0: Option<Account<'info, NullifierAccount>>,
+
+    /// Nullifier PDA for input 1 (per-nullifier mode, omitted if input 1 is unused)
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + NullifierAccount::INIT_SPACE,
+        seeds = [NULLIFIER_SEED, pool_config.key().as_ref(), inputs.input_nullifier_hashes[1].as_ref()],
+        bump
+    )]
+    pub nullifier_account_1: Option<Account<'info, NullifierAccount>>,
+
+    /// Shard for input 0 (sharded mode)
+    #[account(
+        mut,
+        seeds = [
+            NULLIFIER_SHARD_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.nullifier_shard_index(&inputs.input_nullifier_hashes[0]).to_le_bytes(),
+        ],
+        bump = nullifier_shard_0.load()?.bump,
+    )]
+    pub nullifier_shard_0: Option<AccountLoader<'info, NullifierShard>>,
+
+    /// Shard for input 1 (sharded mode; omitted if it is the same shard as input 0)
+    #[account(
+        mut,
+        seeds = [
+            NULLIFIER_SHARD_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.nullifier_shard_index(&inputs.input_nullifier_hashes[1]).to_le_bytes(),
+        ],
+        bump = nullifier_shard_1.load()?.bump,
+    )]
+    pub nullifier_shard_1: Option<AccountLoader<'info, NullifierShard>>,
+
+    /// Commitment record for output 0
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + CommitmentRecord::INIT_SPACE,
+        seeds = [
+            COMMITMENT_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.current_generation.to_le_bytes(),
+            &merkle_tree.load()?.next_index.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub commitment_record_0: Account<'info, CommitmentRecord>,
+
+    /// Commitment record for output 1 (required iff output 1 is set)
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + CommitmentRecord::INIT_SPACE,
+        seeds = [
+            COMMITMENT_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.current_generation.to_le_bytes(),
+            &(merkle_tree.load()?.next_index + 1).to_le_bytes(),
+        ],
+        bump
+    )]
+    pub commitment_record_1: Option<Account<'info, CommitmentRecord>>,
+
+    /// Sunspot verifier program for the join-split circuit
+    /// CHECK: Must match the transact verifier configured in pool config
+    #[account(
+        executable,
+        constraint = pool_config.transact_verifier_program != Pubkey::default() @ ZkPoolError::InvalidVerifierProgram,
+        constraint = verifier_program.key() == pool_config.transact_verifier_program @ ZkPoolError::InvalidVerifierProgram,
+    )]
+    pub verifier_program: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<Transact>, inputs: TransactInputs, proof: Vec<u8>) -> Result<()> {
+    let zero = [0u8; 32];
+    let [nullifier_0, nullifier_1] = inputs.input_nullifier_hashes;
+    let [output_0, output_1] = inputs.output_commitments;
+
+    require!(!ctx.accounts.pool_config.paused, ZkPoolError::ProtocolPaused);
+    require!(
+        inputs.version == TRANSACT_INPUTS_VERSION,
+        ZkPoolError::UnsupportedInputsVersion
+    );
+    require!(nullifier_0 != zero, ZkPoolError::InvalidProof);
+    require!(nullifier_0 != nullifier_1, ZkPoolError::NullifierAlreadySpent);
+    require!(output_0 != zero, ZkPoolError::InvalidCommitment);
+    require!(
+        (output_1 != zero) == ctx.accounts.commitment_record_1.is_some(),
+        ZkPoolError::InvalidCommitment
+    );
+
+    // Verify the state root is known in the referenced tree generation
+    {
+        let tree = ctx.accounts.root_tree.load()?;
+        require!(
+            tree.is_known_root(&inputs.state_root),
+            ZkPoolError::InvalidStateRoot
+        );
+    }
+
+    // Verify the join-split proof (inputs and outputs balance, same asset)
+    verifier::verify_proof(
+        &ctx.accounts.verifier_program.to_account_info(),
+        &proof,
+        &inputs.public_inputs(),
+    )?;
+
+    // Mark the spent nullifiers; input 1 may share input 0's shard
+    let pool_config = &ctx.accounts.pool_config;
+    nullifier::mark_spent(
+        pool_config,
+        ctx.accounts.nullifier_account_0.as_mut(),
+        ctx.bumps.nullifier_account_0,
+        ctx.accounts.nullifier_shard_0.as_ref(),
+        &nullifier_0,
+        &ctx.accounts.relayer,
+        &ctx.accounts.system_program,
+    )?;
+    if nullifier_1 != zero {
+        let same_shard = pool_config.nullifier_shard_index(&nullifier_0)
+            == pool_config.nullifier_shard_index(&nullifier_1);
+        let shard_1 = match ctx.accounts.nullifier_shard_1.as_ref() {
+            Some(shard) => Some(shard),
+            None if same_shard => ctx.accounts.nullifier_shard_0.as_ref(),
+            None => None,
+        };
+        nullifier::mark_spent(
+            pool_config,
+            ctx.accounts.nullifier_account_1.as_mut(),
+            ctx.bumps.nullifier_account_1,
+            shard_1,
+            &nullifier_1,
+            &ctx.accounts.relayer,
+            &ctx.accounts.system_program,
+        )?;
+    } else {
+        require!(
+            ctx.accounts.nullifier_account_1.is_none() && ctx.accounts.nullifier_shard_1.is_none(),
+            ZkPoolError::InvalidNullifierAccount
+        );
+    }
+
+    // Insert the outputs and record them like deposits
+    let mint = (inputs.mint != Pubkey::default()).then_some(inputs.mint);
+    let relayer = ctx.accounts.relayer.key();
+    let mut tree = ctx.accounts.merkle_tree.load_mut()?;
+    let mut output_indices = [None; 2];
+    let records = [
+        Some((&mut ctx.accounts.commitment_record_0, ctx.bumps.commitment_record_0)),
+        ctx.accounts
+            .commitment_record_1
+            .as_mut()
+            .zip(ctx.bumps.commitment_record_1),
+    ];
+    for (i, record) in records.into_iter().enumerate() {
+        let Some((record, bump)) = record else { continue };
+        output_indices[i] = Some(insert_note(
+            ctx.accounts.pool_config.pool_id,
+            &mut tree,
+            record,
+            relayer,
+            bump,
+            Note {
+                commitment: inputs.output_commitments[i],
+                encrypted_output: inputs.encrypted_outputs[i].clone(),
+                amount: None,
+                mint,
+            },
+        )?);
+    }
+
+    emit_root_updated(ctx.accounts.pool_config.pool_id, &mut tree)?;
+
+    emit!(TransactProcessed {
+        header: EventHeader::tree(ctx.accounts.pool_config.pool_id, &mut tree)?,
+        input_nullifier_hashes: inputs.input_nullifier_hashes,
+        output_commitments: inputs.output_commitments,
+        output_indices,
+        mint,
+    });
+
+    msg!("Shielded transfer: outputs={:?}", output_indices);
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use anchor_lang::prelude::*;
+use crate::state::{MintConfig, PoolConfig};
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::events::{EventHeader, MintFeeConfigUpdated};
+
+#[derive(Accounts)]
+pub struct UpdateMintConfig<'info> {
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
+        bump = pool_config.bump,
+        constraint = pool_config.admin == admin.key() @ ZkPoolError::UnauthorizedAdmin,
+    )]
+    pub pool_config: Account<'info, PoolConfig>,
+
+    #[account(
+        mut,
+        seeds = [MINT_CONFIG_SEED, pool_config.key().as_ref(), mint_config.mint.as_ref()],
+        bump = mint_config.bump,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
+}
+
+/// Update a registered mint's withdrawal fees. Limits are set via `set_limits`.
+pub fn handler(ctx: Context<UpdateMintConfig>, fee_bps: u16, min_fee: u64) -> Result<()> {
+    let config = &mut ctx.accounts.mint_config;
+    MintConfig::check_fee_config(config.limits.min_deposit, fee_bps, min_fee)?;
+    config.fee_bps = fee_bps;
+    config.min_fee = min_fee;
+
+    emit!(MintFeeConfigUpdated {
+        header: EventHeader::config(&mut ctx.accounts.pool_config)?,
+        mint: config.mint,
+        fee_bps,
+        min_fee,
+    });
+
+    msg!("Mint {} fees updated: {} bps, min_fee={}", config.mint, fee_bps, min_fee);
+
+    Ok(())
+}
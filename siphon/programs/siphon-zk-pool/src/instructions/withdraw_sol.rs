@@ -1,36 +1,102 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
 use anchor_lang::system_program;
-use crate::state::{MerkleTree, PoolConfig, NullifierAccount};
+use anchor_spl::token::{self, spl_token, SyncNative, Token, TokenAccount};
+use crate::state::{MerkleTree, PoolConfig, CommitmentRecord, NullifierAccount, NullifierShard};
 use crate::constants::*;
 use crate::errors::ZkPoolError;
-use crate::events::{WithdrawalProcessed, CommitmentInserted};
+use crate::nullifier;
+use crate::events::{EventHeader, WithdrawalProcessed};
+use crate::notes::{emit_root_updated, insert_note, Note};
+use crate::verifier::{self, asset_id, u64_to_field};
 
-/// Public inputs from the Noir ZK proof (verified off-chain by relayer)
+/// Withdrawal request. The proof's public inputs are the Merkle/nullifier
+/// fields plus `ext_data_hash()`, which binds where and how much is paid out.
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
 pub struct WithdrawInputs {
+    /// Layout version (must be WITHDRAW_INPUTS_VERSION)
+    pub version: u8,
     pub nullifier_hash: [u8; 32],
     pub state_root: [u8; 32],
+    /// Generation of the tree that `state_root` belongs to
+    pub root_generation: u64,
     pub new_commitment: [u8; 32],
+    /// Recipient wallet (token account owner for SPL withdrawals)
+    pub recipient: Pubkey,
+    /// Token mint, or Pubkey::default() for SOL
+    pub mint: Pubkey,
+    /// Amount paid to the recipient (after fee)
+    pub amount: u64,
+    /// Fee paid to `fee_recipient`
+    pub fee: u64,
+    /// Fee payee (wallet owner for SPL withdrawals)
+    pub fee_recipient: Pubkey,
+    /// Encrypted change note (empty when there is no change output)
+    pub encrypted_output: Vec<u8>,
+}
+
+impl WithdrawInputs {
+    /// sha256(recipient || mint || amount || fee || fee_recipient || encrypted_output)
+    /// reduced to a field element
+    pub fn ext_data_hash(&self) -> [u8; 32] {
+        let mut hash = hashv(&[
+            self.recipient.as_ref(),
+            self.mint.as_ref(),
+            &self.amount.to_be_bytes(),
+            &self.fee.to_be_bytes(),
+            self.fee_recipient.as_ref(),
+            &self.encrypted_output,
+        ])
+        .to_bytes();
+        hash[0] = 0;
+        hash
+    }
+
+    /// Public inputs in circuit order:
+    /// (withdrawn_value, state_root, new_commitment, nullifier_hash, asset_id, ext_data_hash)
+    pub fn public_inputs(&self, withdrawn_value: u64) -> [[u8; 32]; 6] {
+        [
+            u64_to_field(withdrawn_value),
+            self.state_root,
+            self.new_commitment,
+            self.nullifier_hash,
+            asset_id(&self.mint),
+            self.ext_data_hash(),
+        ]
+    }
 }
 
 #[derive(Accounts)]
-#[instruction(inputs: WithdrawInputs, recipient: Pubkey, amount: u64, fee: u64)]
+#[instruction(inputs: WithdrawInputs)]
 pub struct WithdrawSol<'info> {
-    /// Relayer authority — only the relayer can submit withdrawals
-    /// (relayer has verified the Noir/Groth16 proof off-chain)
+    /// Transaction submitter — the pool relayer, or any signer self-relaying.
+    /// Pays rent for the nullifier account.
     #[account(mut)]
     pub relayer: Signer<'info>,
 
+    /// Mutable to track SOL withdrawal limits
     #[account(
-        seeds = [POOL_CONFIG_SEED],
+        mut,
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
         bump = pool_config.bump,
-        constraint = pool_config.relayer == relayer.key() @ ZkPoolError::UnauthorizedRelayer,
     )]
     pub pool_config: Account<'info, PoolConfig>,
 
+    /// Tree whose root history contains `inputs.state_root` (any generation)
+    #[account(
+        seeds = [MERKLE_TREE_SEED, pool_config.key().as_ref(), &inputs.root_generation.to_le_bytes()],
+        bump = root_tree.load()?.bump,
+    )]
+    pub root_tree: AccountLoader<'info, MerkleTree>,
+
+    /// Active tree that receives the change commitment
     #[account(
         mut,
-        seeds = [MERKLE_TREE_SEED],
+        seeds = [
+            MERKLE_TREE_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.current_generation.to_le_bytes(),
+        ],
         bump = pool_config.tree_bump,
     )]
     pub merkle_tree: AccountLoader<'info, MerkleTree>,
@@ -39,86 +105,188 @@ pub struct WithdrawSol<'info> {
     /// CHECK: PDA that holds lamports
     #[account(
         mut,
-        seeds = [POOL_VAULT_SEED],
+        seeds = [POOL_VAULT_SEED, pool_config.key().as_ref()],
         bump = pool_config.vault_bump,
     )]
     pub pool_vault: SystemAccount<'info>,
 
-    /// Nullifier PDA — created here to mark this nullifier as spent.
+    /// Nullifier PDA — per-nullifier mode (`pool_config.nullifier_shards == 0`).
     /// If it already exists, the transaction fails (double-spend prevention).
     #[account(
         init,
         payer = relayer,
         space = 8 + NullifierAccount::INIT_SPACE,
-        seeds = [NULLIFIER_SEED, inputs.nullifier_hash.as_ref()],
+        seeds = [NULLIFIER_SEED, pool_config.key().as_ref(), inputs.nullifier_hash.as_ref()],
         bump
     )]
-    pub nullifier_account: Account<'info, NullifierAccount>,
+    pub nullifier_account: Option<Account<'info, NullifierAccount>>,
+
+    /// Nullifier shard — sharded mode; must be the shard for `inputs.nullifier_hash`
+    #[account(
+        mut,
+        seeds = [
+            NULLIFIER_SHARD_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.nullifier_shard_index(&inputs.nullifier_hash).to_le_bytes(),
+        ],
+        bump = nullifier_shard.load()?.bump,
+    )]
+    pub nullifier_shard: Option<AccountLoader<'info, NullifierShard>>,
+
+    /// Commitment record for the change output (required iff `inputs.new_commitment` is set)
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + CommitmentRecord::INIT_SPACE,
+        seeds = [
+            COMMITMENT_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.current_generation.to_le_bytes(),
+            &merkle_tree.load()?.next_index.to_le_bytes(),
+        ],
+        bump
+    )]
+    pub commitment_record: Option<Account<'info, CommitmentRecord>>,
 
     /// Recipient of the withdrawal
-    /// CHECK: Any valid account can receive SOL
+    /// CHECK: Any valid account can receive SOL; must match `inputs.recipient`
     #[account(mut)]
     pub recipient: SystemAccount<'info>,
 
+    /// Recipient's wSOL account; when set, `amount` is paid out wrapped here instead
+    #[account(
+        mut,
+        constraint = recipient_wsol_account.mint == spl_token::native_mint::ID @ ZkPoolError::InvalidMint,
+        constraint = recipient_wsol_account.owner == inputs.recipient @ ZkPoolError::InvalidRecipient,
+    )]
+    pub recipient_wsol_account: Option<Account<'info, TokenAccount>>,
+
     /// Fee recipient
-    /// CHECK: Account specified in pool config
+    /// CHECK: Must be the fee payee bound into the proof
     #[account(
         mut,
-        constraint = fee_recipient.key() == pool_config.fee_recipient @ ZkPoolError::InvalidFeeConfig,
+        constraint = fee_recipient.key() == inputs.fee_recipient @ ZkPoolError::InvalidFeeConfig,
     )]
     pub fee_recipient: SystemAccount<'info>,
 
+    /// Sunspot verifier program for the withdrawal circuit
+    /// CHECK: Must match the verifier configured in pool config
+    #[account(
+        executable,
+        constraint = pool_config.verifier_program != Pubkey::default() @ ZkPoolError::InvalidVerifierProgram,
+        constraint = verifier_program.key() == pool_config.verifier_program @ ZkPoolError::InvalidVerifierProgram,
+    )]
+    pub verifier_program: UncheckedAccount<'info>,
+
+    /// Required with `recipient_wsol_account`
+    pub token_program: Option<Program<'info, Token>>,
     pub system_program: Program<'info, System>,
 }
 
 pub fn handler(
     ctx: Context<WithdrawSol>,
     inputs: WithdrawInputs,
-    _recipient: Pubkey,
-    amount: u64,
-    fee: u64,
+    proof: Vec<u8>,
 ) -> Result<()> {
-    require!(!ctx.accounts.pool_config.paused, ZkPoolError::ProtocolPaused);
+    let WithdrawInputs { recipient, amount, fee, .. } = inputs;
+
+    require!(
+        ctx.accounts
+            .pool_config
+            .withdrawals_open(Clock::get()?.unix_timestamp),
+        ZkPoolError::ProtocolPaused
+    );
+    require!(
+        inputs.version == WITHDRAW_INPUTS_VERSION,
+        ZkPoolError::UnsupportedInputsVersion
+    );
     require!(amount > 0, ZkPoolError::InvalidAmount);
+    require_keys_eq!(inputs.mint, Pubkey::default(), ZkPoolError::InvalidMint);
+    require_keys_eq!(ctx.accounts.recipient.key(), recipient, ZkPoolError::InvalidRecipient);
+    ctx.accounts.pool_config.check_fee(amount, fee)?;
+
+    ctx.accounts
+        .pool_config
+        .check_fee_recipient(&ctx.accounts.relayer.key(), &inputs.fee_recipient)?;
 
     // Verify the state root is known (exists in root history)
     {
-        let tree = ctx.accounts.merkle_tree.load()?;
+        let tree = ctx.accounts.root_tree.load()?;
         require!(
             tree.is_known_root(&inputs.state_root),
             ZkPoolError::InvalidStateRoot
         );
     }
 
-    // Mark nullifier as spent
-    let nullifier = &mut ctx.accounts.nullifier_account;
-    nullifier.nullifier_hash = inputs.nullifier_hash;
-    nullifier.bump = ctx.bumps.nullifier_account;
+    // Verify the withdrawal proof; the note's withdrawn value covers amount + fee
+    let total_out = amount.checked_add(fee).ok_or(ZkPoolError::Overflow)?;
+    // Operator-set limits stop applying once the escape hatch is open, so they
+    // cannot be used to keep it shut
+    let clock = Clock::get()?;
+    if !ctx.accounts.pool_config.escape_hatch_open(clock.unix_timestamp) {
+        ctx.accounts
+            .pool_config
+            .sol_limits
+            .record_withdrawal(total_out, clock.epoch)?;
+    }
+    verifier::verify_proof(
+        &ctx.accounts.verifier_program.to_account_info(),
+        &proof,
+        &inputs.public_inputs(total_out),
+    )?;
+
+    // Mark nullifier as spent in the store this pool uses
+    nullifier::mark_spent(
+        &ctx.accounts.pool_config,
+        ctx.accounts.nullifier_account.as_mut(),
+        ctx.bumps.nullifier_account,
+        ctx.accounts.nullifier_shard.as_ref(),
+        &inputs.nullifier_hash,
+        &ctx.accounts.relayer,
+        &ctx.accounts.system_program,
+    )?;
 
     // Transfer SOL from pool vault to recipient
-    let total_out = amount.checked_add(fee).ok_or(ZkPoolError::Overflow)?;
     let vault_lamports = ctx.accounts.pool_vault.lamports();
     require!(vault_lamports >= total_out, ZkPoolError::InsufficientBalance);
 
     // PDA signer seeds for pool_vault
+    let pool_key = ctx.accounts.pool_config.key();
     let vault_seeds = &[
         POOL_VAULT_SEED,
+        pool_key.as_ref(),
         &[ctx.accounts.pool_config.vault_bump],
     ];
     let signer_seeds = &[&vault_seeds[..]];
 
-    // Transfer amount to recipient using CPI with PDA signer
+    // Transfer amount to recipient using CPI with PDA signer, wrapping it as wSOL
+    // when the recipient asked for a token account payout
+    let payout = match ctx.accounts.recipient_wsol_account.as_ref() {
+        Some(wsol_account) => wsol_account.to_account_info(),
+        None => ctx.accounts.recipient.to_account_info(),
+    };
     system_program::transfer(
         CpiContext::new_with_signer(
             ctx.accounts.system_program.to_account_info(),
             system_program::Transfer {
                 from: ctx.accounts.pool_vault.to_account_info(),
-                to: ctx.accounts.recipient.to_account_info(),
+                to: payout.clone(),
             },
             signer_seeds,
         ),
         amount,
     )?;
+    if ctx.accounts.recipient_wsol_account.is_some() {
+        let token_program = ctx
+            .accounts
+            .token_program
+            .as_ref()
+            .ok_or(ZkPoolError::InvalidRecipient)?;
+        token::sync_native(CpiContext::new(
+            token_program.to_account_info(),
+            SyncNative { account: payout },
+        ))?;
+    }
 
     // Transfer fee to fee_recipient using CPI with PDA signer
     if fee > 0 {
@@ -140,26 +308,40 @@ pub fn handler(
     let zero_commitment = [0u8; 32];
     if inputs.new_commitment != zero_commitment {
         let mut tree = ctx.accounts.merkle_tree.load_mut()?;
-        require!(!tree.is_full(), ZkPoolError::TreeFull);
-        let idx = tree.next_index;
-        tree.next_index = idx.checked_add(1).ok_or(ZkPoolError::Overflow)?;
+        let record = ctx
+            .accounts
+            .commitment_record
+            .as_mut()
+            .ok_or(ZkPoolError::InvalidCommitment)?;
+        let bump = ctx.bumps.commitment_record.ok_or(ZkPoolError::InvalidCommitment)?;
+        let pool_id = ctx.accounts.pool_config.pool_id;
+        let idx = insert_note(
+            pool_id,
+            &mut tree,
+            record,
+            ctx.accounts.relayer.key(),
+            bump,
+            Note {
+                commitment: inputs.new_commitment,
+                encrypted_output: inputs.encrypted_output.clone(),
+                amount: None, // Change amount is hidden
+                mint: None,
+            },
+        )?;
         new_index = Some(idx);
-
-        // Note: CommitmentRecord for change output is NOT created here
-        // because we can't init another PDA in the same instruction easily.
-        // The relayer indexes the change commitment from the event.
-        emit!(CommitmentInserted {
-            index: idx,
-            commitment: inputs.new_commitment,
-            encrypted_output: vec![],
-            amount: 0, // Change amount is hidden
-            mint: None,
-        });
+        emit_root_updated(pool_id, &mut tree)?;
+    } else {
+        require!(
+            ctx.accounts.commitment_record.is_none(),
+            ZkPoolError::InvalidCommitment
+        );
     }
 
+    let mut tree = ctx.accounts.merkle_tree.load_mut()?;
     emit!(WithdrawalProcessed {
+        header: EventHeader::tree(ctx.accounts.pool_config.pool_id, &mut tree)?,
         nullifier_hash: inputs.nullifier_hash,
-        recipient: ctx.accounts.recipient.key(),
+        recipient,
         amount,
         fee,
         mint: None,
@@ -175,3 +357,62 @@ pub fn handler(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inputs() -> WithdrawInputs {
+        WithdrawInputs {
+            version: WITHDRAW_INPUTS_VERSION,
+            nullifier_hash: [1; 32],
+            state_root: [2; 32],
+            root_generation: 0,
+            new_commitment: [3; 32],
+            recipient: Pubkey::new_from_array([4; 32]),
+            mint: Pubkey::default(),
+            amount: 1_000,
+            fee: 3,
+            fee_recipient: Pubkey::new_from_array([5; 32]),
+            encrypted_output: vec![6; 16],
+        }
+    }
+
+    #[test]
+    fn ext_data_hash_fits_the_circuit_range_check() {
+        // The circuit asserts ext_data_hash fits in 248 bits
+        assert_eq!(inputs().ext_data_hash()[0], 0);
+    }
+
+    #[test]
+    fn ext_data_hash_changes_with_every_bound_field() {
+        let original = inputs().ext_data_hash();
+        let tampered: [fn(&mut WithdrawInputs); 6] = [
+            |i| i.recipient = Pubkey::new_from_array([9; 32]),
+            |i| i.mint = Pubkey::new_from_array([9; 32]),
+            |i| i.amount += 1,
+            |i| i.fee += 1,
+            |i| i.fee_recipient = Pubkey::new_from_array([9; 32]),
+            |i| i.encrypted_output[0] ^= 1,
+        ];
+        for tamper in tampered {
+            let mut changed = inputs();
+            tamper(&mut changed);
+            assert_ne!(changed.ext_data_hash(), original);
+            // ...so the proof's last public input no longer matches
+            assert_ne!(changed.public_inputs(1_003)[5], inputs().public_inputs(1_003)[5]);
+        }
+    }
+
+    #[test]
+    fn public_inputs_follow_circuit_order() {
+        let inputs = inputs();
+        let public = inputs.public_inputs(1_003);
+        assert_eq!(public[0], u64_to_field(1_003));
+        assert_eq!(public[1], inputs.state_root);
+        assert_eq!(public[2], inputs.new_commitment);
+        assert_eq!(public[3], inputs.nullifier_hash);
+        assert_eq!(public[4], asset_id(&inputs.mint));
+        assert_eq!(public[5], inputs.ext_data_hash());
+    }
+}
@@ -1,27 +1,44 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
-use crate::state::{MerkleTree, PoolConfig, NullifierAccount};
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use anchor_spl::associated_token::AssociatedToken;
+use crate::state::{MerkleTree, PoolConfig, CommitmentRecord, NullifierAccount, NullifierShard, MintConfig};
 use crate::constants::*;
 use crate::errors::ZkPoolError;
-use crate::events::{WithdrawalProcessed, CommitmentInserted};
+use crate::nullifier;
+use crate::events::{EventHeader, WithdrawalProcessed};
+use crate::notes::{emit_root_updated, insert_note, Note};
 use crate::instructions::withdraw_sol::WithdrawInputs;
+use crate::verifier;
 
 #[derive(Accounts)]
-#[instruction(inputs: WithdrawInputs, recipient: Pubkey, amount: u64, fee: u64)]
+#[instruction(inputs: WithdrawInputs)]
 pub struct WithdrawSpl<'info> {
+    /// Transaction submitter — the pool relayer, or any signer self-relaying.
+    /// Pays rent for the nullifier account.
     #[account(mut)]
     pub relayer: Signer<'info>,
 
     #[account(
-        seeds = [POOL_CONFIG_SEED],
+        seeds = [POOL_CONFIG_SEED, &pool_config.pool_id.to_le_bytes()],
         bump = pool_config.bump,
-        constraint = pool_config.relayer == relayer.key() @ ZkPoolError::UnauthorizedRelayer,
     )]
     pub pool_config: Account<'info, PoolConfig>,
 
+    /// Tree whose root history contains `inputs.state_root` (any generation)
+    #[account(
+        seeds = [MERKLE_TREE_SEED, pool_config.key().as_ref(), &inputs.root_generation.to_le_bytes()],
+        bump = root_tree.load()?.bump,
+    )]
+    pub root_tree: AccountLoader<'info, MerkleTree>,
+
+    /// Active tree that receives the change commitment
     #[account(
         mut,
-        seeds = [MERKLE_TREE_SEED],
+        seeds = [
+            MERKLE_TREE_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.current_generation.to_le_bytes(),
+        ],
         bump = pool_config.tree_bump,
     )]
     pub merkle_tree: AccountLoader<'info, MerkleTree>,
@@ -29,12 +46,21 @@ pub struct WithdrawSpl<'info> {
     /// Pool vault (authority for token accounts)
     /// CHECK: PDA used as token authority
     #[account(
-        seeds = [POOL_VAULT_SEED],
+        seeds = [POOL_VAULT_SEED, pool_config.key().as_ref()],
         bump = pool_config.vault_bump,
     )]
     pub pool_vault: SystemAccount<'info>,
 
-    pub token_mint: Account<'info, Mint>,
+    /// SPL Token or Token-2022 mint
+    pub token_mint: InterfaceAccount<'info, Mint>,
+
+    /// Per-mint config (fees and withdrawal limits apply even if deposits are disabled)
+    #[account(
+        mut,
+        seeds = [MINT_CONFIG_SEED, pool_config.key().as_ref(), token_mint.key().as_ref()],
+        bump = mint_config.bump,
+    )]
+    pub mint_config: Account<'info, MintConfig>,
 
     /// Pool's token account for this mint
     #[account(
@@ -42,90 +68,181 @@ pub struct WithdrawSpl<'info> {
         constraint = pool_token_account.mint == token_mint.key(),
         constraint = pool_token_account.owner == pool_vault.key(),
     )]
-    pub pool_token_account: Account<'info, TokenAccount>,
+    pub pool_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    /// Recipient's token account
+    /// Recipient wallet (owner of the recipient token account)
+    /// CHECK: Any wallet can receive tokens; must match `inputs.recipient`
+    pub recipient: UncheckedAccount<'info>,
+
+    /// Recipient's associated token account, created on demand
     #[account(
-        mut,
-        constraint = recipient_token_account.mint == token_mint.key(),
+        init_if_needed,
+        payer = relayer,
+        associated_token::mint = token_mint,
+        associated_token::authority = recipient,
+        associated_token::token_program = token_program,
     )]
-    pub recipient_token_account: Account<'info, TokenAccount>,
+    pub recipient_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    /// Fee recipient's token account
+    /// Fee recipient's token account (owned by the fee payee bound into the proof)
     #[account(
         mut,
         constraint = fee_recipient_token_account.mint == token_mint.key(),
+        constraint = fee_recipient_token_account.owner == inputs.fee_recipient @ ZkPoolError::InvalidFeeConfig,
     )]
-    pub fee_recipient_token_account: Account<'info, TokenAccount>,
+    pub fee_recipient_token_account: InterfaceAccount<'info, TokenAccount>,
 
-    /// Nullifier PDA — double-spend prevention
+    /// Nullifier PDA — per-nullifier mode (`pool_config.nullifier_shards == 0`).
+    /// If it already exists, the transaction fails (double-spend prevention).
     #[account(
         init,
         payer = relayer,
         space = 8 + NullifierAccount::INIT_SPACE,
-        seeds = [NULLIFIER_SEED, inputs.nullifier_hash.as_ref()],
+        seeds = [NULLIFIER_SEED, pool_config.key().as_ref(), inputs.nullifier_hash.as_ref()],
+        bump
+    )]
+    pub nullifier_account: Option<Account<'info, NullifierAccount>>,
+
+    /// Nullifier shard — sharded mode; must be the shard for `inputs.nullifier_hash`
+    #[account(
+        mut,
+        seeds = [
+            NULLIFIER_SHARD_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.nullifier_shard_index(&inputs.nullifier_hash).to_le_bytes(),
+        ],
+        bump = nullifier_shard.load()?.bump,
+    )]
+    pub nullifier_shard: Option<AccountLoader<'info, NullifierShard>>,
+
+    /// Commitment record for the change output (required iff `inputs.new_commitment` is set)
+    #[account(
+        init,
+        payer = relayer,
+        space = 8 + CommitmentRecord::INIT_SPACE,
+        seeds = [
+            COMMITMENT_SEED,
+            pool_config.key().as_ref(),
+            &pool_config.current_generation.to_le_bytes(),
+            &merkle_tree.load()?.next_index.to_le_bytes(),
+        ],
         bump
     )]
-    pub nullifier_account: Account<'info, NullifierAccount>,
+    pub commitment_record: Option<Account<'info, CommitmentRecord>>,
 
-    pub token_program: Program<'info, Token>,
+    /// Sunspot verifier program for the withdrawal circuit
+    /// CHECK: Must match the verifier configured in pool config
+    #[account(
+        executable,
+        constraint = pool_config.verifier_program != Pubkey::default() @ ZkPoolError::InvalidVerifierProgram,
+        constraint = verifier_program.key() == pool_config.verifier_program @ ZkPoolError::InvalidVerifierProgram,
+    )]
+    pub verifier_program: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
 pub fn handler(
     ctx: Context<WithdrawSpl>,
     inputs: WithdrawInputs,
-    _recipient: Pubkey,
-    amount: u64,
-    fee: u64,
+    proof: Vec<u8>,
 ) -> Result<()> {
-    require!(!ctx.accounts.pool_config.paused, ZkPoolError::ProtocolPaused);
+    let WithdrawInputs { recipient, amount, fee, .. } = inputs;
+
+    require!(
+        ctx.accounts
+            .pool_config
+            .withdrawals_open(Clock::get()?.unix_timestamp),
+        ZkPoolError::ProtocolPaused
+    );
+    require!(
+        inputs.version == WITHDRAW_INPUTS_VERSION,
+        ZkPoolError::UnsupportedInputsVersion
+    );
     require!(amount > 0, ZkPoolError::InvalidAmount);
+    require_keys_eq!(inputs.mint, ctx.accounts.token_mint.key(), ZkPoolError::InvalidMint);
+    require_keys_eq!(ctx.accounts.recipient.key(), recipient, ZkPoolError::InvalidRecipient);
+    ctx.accounts.mint_config.check_fee(amount, fee)?;
+
+    ctx.accounts
+        .pool_config
+        .check_fee_recipient(&ctx.accounts.relayer.key(), &inputs.fee_recipient)?;
 
     // Verify the state root is known
     {
-        let tree = ctx.accounts.merkle_tree.load()?;
+        let tree = ctx.accounts.root_tree.load()?;
         require!(
             tree.is_known_root(&inputs.state_root),
             ZkPoolError::InvalidStateRoot
         );
     }
 
-    // Mark nullifier as spent
-    let nullifier = &mut ctx.accounts.nullifier_account;
-    nullifier.nullifier_hash = inputs.nullifier_hash;
-    nullifier.bump = ctx.bumps.nullifier_account;
+    // Verify the withdrawal proof; the note's withdrawn value covers amount + fee
+    let total_out = amount.checked_add(fee).ok_or(ZkPoolError::Overflow)?;
+    // Operator-set limits stop applying once the escape hatch is open, so they
+    // cannot be used to keep it shut
+    let clock = Clock::get()?;
+    if !ctx.accounts.pool_config.escape_hatch_open(clock.unix_timestamp) {
+        ctx.accounts
+            .mint_config
+            .limits
+            .record_withdrawal(total_out, clock.epoch)?;
+    }
+    verifier::verify_proof(
+        &ctx.accounts.verifier_program.to_account_info(),
+        &proof,
+        &inputs.public_inputs(total_out),
+    )?;
+
+    // Mark nullifier as spent in the store this pool uses
+    nullifier::mark_spent(
+        &ctx.accounts.pool_config,
+        ctx.accounts.nullifier_account.as_mut(),
+        ctx.bumps.nullifier_account,
+        ctx.accounts.nullifier_shard.as_ref(),
+        &inputs.nullifier_hash,
+        &ctx.accounts.relayer,
+        &ctx.accounts.system_program,
+    )?;
 
-    // Transfer SPL tokens from pool to recipient
+    // Transfer tokens from pool to recipient. For Token-2022 transfer-fee mints the
+    // token fee is withheld from what the recipient and fee recipient receive.
+    let pool_key = ctx.accounts.pool_config.key();
     let vault_bump = ctx.accounts.pool_config.vault_bump;
-    let vault_seeds: &[&[&[u8]]] = &[&[POOL_VAULT_SEED, &[vault_bump]]];
+    let vault_seeds: &[&[&[u8]]] = &[&[POOL_VAULT_SEED, pool_key.as_ref(), &[vault_bump]]];
 
-    token::transfer(
+    token_interface::transfer_checked(
         CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
-            Transfer {
+            TransferChecked {
                 from: ctx.accounts.pool_token_account.to_account_info(),
+                mint: ctx.accounts.token_mint.to_account_info(),
                 to: ctx.accounts.recipient_token_account.to_account_info(),
                 authority: ctx.accounts.pool_vault.to_account_info(),
             },
             vault_seeds,
         ),
         amount,
+        ctx.accounts.token_mint.decimals,
     )?;
 
     // Transfer fee
     if fee > 0 {
-        token::transfer(
+        token_interface::transfer_checked(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
-                Transfer {
+                TransferChecked {
                     from: ctx.accounts.pool_token_account.to_account_info(),
+                    mint: ctx.accounts.token_mint.to_account_info(),
                     to: ctx.accounts.fee_recipient_token_account.to_account_info(),
                     authority: ctx.accounts.pool_vault.to_account_info(),
                 },
                 vault_seeds,
             ),
             fee,
+            ctx.accounts.token_mint.decimals,
         )?;
     }
 
@@ -134,25 +251,42 @@ pub fn handler(
     let zero_commitment = [0u8; 32];
     if inputs.new_commitment != zero_commitment {
         let mut tree = ctx.accounts.merkle_tree.load_mut()?;
-        require!(!tree.is_full(), ZkPoolError::TreeFull);
-        let idx = tree.next_index;
-        tree.next_index = idx.checked_add(1).ok_or(ZkPoolError::Overflow)?;
+        let record = ctx
+            .accounts
+            .commitment_record
+            .as_mut()
+            .ok_or(ZkPoolError::InvalidCommitment)?;
+        let bump = ctx.bumps.commitment_record.ok_or(ZkPoolError::InvalidCommitment)?;
+        let pool_id = ctx.accounts.pool_config.pool_id;
+        let idx = insert_note(
+            pool_id,
+            &mut tree,
+            record,
+            ctx.accounts.relayer.key(),
+            bump,
+            Note {
+                commitment: inputs.new_commitment,
+                encrypted_output: inputs.encrypted_output.clone(),
+                amount: None, // Change amount is hidden
+                mint: Some(ctx.accounts.token_mint.key()),
+            },
+        )?;
         new_index = Some(idx);
-
-        emit!(CommitmentInserted {
-            index: idx,
-            commitment: inputs.new_commitment,
-            encrypted_output: vec![],
-            amount: 0,
-            mint: Some(ctx.accounts.token_mint.key()),
-        });
+        emit_root_updated(pool_id, &mut tree)?;
+    } else {
+        require!(
+            ctx.accounts.commitment_record.is_none(),
+            ZkPoolError::InvalidCommitment
+        );
     }
 
     let mint_key = ctx.accounts.token_mint.key();
 
+    let mut tree = ctx.accounts.merkle_tree.load_mut()?;
     emit!(WithdrawalProcessed {
+        header: EventHeader::tree(ctx.accounts.pool_config.pool_id, &mut tree)?,
         nullifier_hash: inputs.nullifier_hash,
-        recipient: ctx.accounts.recipient_token_account.owner,
+        recipient,
         amount,
         fee,
         mint: Some(mint_key),
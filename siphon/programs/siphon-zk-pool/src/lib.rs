@@ -3,7 +3,13 @@ use anchor_lang::prelude::*;
 pub mod constants;
 pub mod errors;
 pub mod events;
+pub mod migration;
+pub mod notes;
+pub mod nullifier;
+pub mod poseidon;
 pub mod state;
+pub mod tokens;
+pub mod verifier;
 pub mod instructions;
 
 use instructions::*;
@@ -14,62 +20,238 @@ declare_id!("3CVsp1zayXhNsT8Ktrh85rTewvBJxWy8VcUtQAKdnQMb");
 pub mod siphon_zk_pool {
     use super::*;
 
-    /// Initialize the ZK privacy pool: Merkle tree, config, and SOL vault
-    pub fn initialize(
-        ctx: Context<Initialize>,
-        relayer: Pubkey,
-        fee_recipient: Pubkey,
-        fee_bps: u16,
-    ) -> Result<()> {
-        instructions::initialize::handler(ctx, relayer, fee_recipient, fee_bps)
+    /// Initialize a ZK privacy pool identified by `pool_id`: Merkle tree, config, and SOL vault
+    pub fn initialize(ctx: Context<Initialize>, params: InitializeParams) -> Result<()> {
+        instructions::initialize::handler(ctx, params)
     }
 
-    /// Deposit SOL into the privacy pool with a commitment
+    /// Deposit SOL into the privacy pool; the commitment is derived on-chain
     pub fn deposit_sol(
         ctx: Context<DepositSol>,
-        commitment: [u8; 32],
+        precommitment: [u8; 32],
         encrypted_output: Vec<u8>,
         amount: u64,
         leaf_index: u64,
     ) -> Result<()> {
-        instructions::deposit_sol::handler(ctx, commitment, encrypted_output, amount, leaf_index)
+        instructions::deposit_sol::handler(ctx, precommitment, encrypted_output, amount, leaf_index)
     }
 
-    /// Deposit SPL tokens into the privacy pool with a commitment
+    /// Deposit SPL tokens of an allow-listed mint; the commitment is derived on-chain
     pub fn deposit_spl(
         ctx: Context<DepositSpl>,
-        commitment: [u8; 32],
+        precommitment: [u8; 32],
         encrypted_output: Vec<u8>,
         amount: u64,
         leaf_index: u64,
     ) -> Result<()> {
-        instructions::deposit_spl::handler(ctx, commitment, encrypted_output, amount, leaf_index)
-    }
-
-    /// Update the Merkle root (relayer only, after computing off-chain)
-    pub fn update_root(ctx: Context<UpdateRoot>, new_root: [u8; 32]) -> Result<()> {
-        instructions::update_root::handler(ctx, new_root)
+        instructions::deposit_spl::handler(ctx, precommitment, encrypted_output, amount, leaf_index)
     }
 
-    /// Withdraw SOL from the privacy pool (relayer submits after off-chain proof verification)
+    /// Withdraw SOL from the privacy pool (Groth16 proof verified on-chain)
     pub fn withdraw_sol(
         ctx: Context<WithdrawSol>,
         inputs: WithdrawInputs,
-        recipient: Pubkey,
-        amount: u64,
-        fee: u64,
+        proof: Vec<u8>,
     ) -> Result<()> {
-        instructions::withdraw_sol::handler(ctx, inputs, recipient, amount, fee)
+        instructions::withdraw_sol::handler(ctx, inputs, proof)
     }
 
-    /// Withdraw SPL tokens from the privacy pool
+    /// Withdraw SPL tokens from the privacy pool (Groth16 proof verified on-chain)
     pub fn withdraw_spl(
         ctx: Context<WithdrawSpl>,
         inputs: WithdrawInputs,
-        recipient: Pubkey,
+        proof: Vec<u8>,
+    ) -> Result<()> {
+        instructions::withdraw_spl::handler(ctx, inputs, proof)
+    }
+
+    /// Pause or unpause the pool (admin), or pause it (guardian)
+    pub fn set_paused(ctx: Context<SetPaused>, paused: bool) -> Result<()> {
+        instructions::set_paused::handler(ctx, paused)
+    }
+
+    /// Rotate the relayer key (admin only)
+    pub fn set_relayer(ctx: Context<SetRelayer>, new_relayer: Pubkey) -> Result<()> {
+        instructions::set_relayer::handler(ctx, new_relayer)
+    }
+
+    /// Update withdrawal fee and fee recipient (admin only)
+    pub fn set_fee_config(
+        ctx: Context<SetFeeConfig>,
+        fee_bps: u16,
+        fee_recipient: Pubkey,
+    ) -> Result<()> {
+        instructions::set_fee_config::handler(ctx, fee_bps, fee_recipient)
+    }
+
+    /// Propose a new Sunspot withdrawal verifier, applied after a time lock (admin only)
+    pub fn set_verifier_program(
+        ctx: Context<SetVerifierProgram>,
+        verifier_program: Pubkey,
+    ) -> Result<()> {
+        instructions::set_verifier_program::handler(ctx, verifier_program)
+    }
+
+    /// Propose a new admin (step 1 of 2, current admin only)
+    pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+        instructions::propose_admin::handler(ctx, new_admin)
+    }
+
+    /// Accept a pending admin proposal (step 2 of 2, proposed admin only)
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        instructions::accept_admin::handler(ctx)
+    }
+
+    /// Allow-list a mint for deposits with its limits and fees (admin only)
+    pub fn register_mint(
+        ctx: Context<RegisterMint>,
+        min_deposit: u64,
+        max_deposit: u64,
+        fee_bps: u16,
+        min_fee: u64,
+    ) -> Result<()> {
+        instructions::register_mint::handler(ctx, min_deposit, max_deposit, fee_bps, min_fee)
+    }
+
+    /// Enable or disable deposits for a registered mint (admin only)
+    pub fn set_mint_enabled(ctx: Context<SetMintEnabled>, enabled: bool) -> Result<()> {
+        instructions::set_mint_enabled::handler(ctx, enabled)
+    }
+
+    /// Restrict SOL or a mint's deposits to fixed denominations, or clear them (admin only)
+    pub fn set_denominations(ctx: Context<SetDenominations>, denominations: Vec<u64>) -> Result<()> {
+        instructions::set_denominations::handler(ctx, denominations)
+    }
+
+    /// Open the next Merkle tree generation once the active tree is full
+    pub fn rollover_tree(ctx: Context<RolloverTree>) -> Result<()> {
+        instructions::rollover_tree::handler(ctx)
+    }
+
+    /// Close a deposit's commitment record and refund its rent to the depositor
+    pub fn close_commitment_record(ctx: Context<CloseCommitmentRecord>) -> Result<()> {
+        instructions::close_commitment_record::handler(ctx)
+    }
+
+    /// Create a nullifier shard for a pool using sharded nullifier storage
+    pub fn init_nullifier_shard(ctx: Context<InitNullifierShard>, shard: u16) -> Result<()> {
+        instructions::init_nullifier_shard::handler(ctx, shard)
+    }
+
+    /// Shielded 2-in/2-out transfer between notes with zero public amount.
+    /// Also splits (1 in, 2 out) a wallet's own notes; see `merge` for consolidation.
+    pub fn transact(ctx: Context<Transact>, inputs: TransactInputs, proof: Vec<u8>) -> Result<()> {
+        instructions::transact::handler(ctx, inputs, proof)
+    }
+
+    /// Set the verifier program for the join-split circuit (admin only)
+    pub fn set_transact_verifier_program(
+        ctx: Context<SetTransactVerifierProgram>,
+        verifier_program: Pubkey,
+    ) -> Result<()> {
+        instructions::set_transact_verifier_program::handler(ctx, verifier_program)
+    }
+
+    /// Merge up to MERGE_INPUTS of a wallet's notes of one asset into a single note
+    pub fn merge(ctx: Context<Merge>, inputs: MergeInputs, proof: Vec<u8>) -> Result<()> {
+        instructions::merge::handler(ctx, inputs, proof)
+    }
+
+    /// Set the verifier program for the merge circuit (admin only)
+    pub fn set_merge_verifier_program(
+        ctx: Context<SetMergeVerifierProgram>,
+        verifier_program: Pubkey,
+    ) -> Result<()> {
+        instructions::set_merge_verifier_program::handler(ctx, verifier_program)
+    }
+
+    /// Deposit SPL tokens with a sponsor paying rent, optionally reimbursed from the deposit
+    pub fn deposit_spl_sponsored(
+        ctx: Context<DepositSplSponsored>,
+        precommitment: [u8; 32],
+        encrypted_output: Vec<u8>,
+        amount: u64,
+        leaf_index: u64,
+        sponsor_fee: u64,
+    ) -> Result<()> {
+        instructions::deposit_spl_sponsored::handler(
+            ctx,
+            precommitment,
+            encrypted_output,
+            amount,
+            leaf_index,
+            sponsor_fee,
+        )
+    }
+
+    /// Set per-transaction and per-epoch limits for SOL or a registered mint (admin only)
+    pub fn set_limits(
+        ctx: Context<SetLimits>,
+        min_deposit: u64,
+        max_deposit: u64,
+        max_withdrawal: u64,
+        epoch_withdrawal_cap: u64,
+    ) -> Result<()> {
+        instructions::set_limits::handler(
+            ctx,
+            min_deposit,
+            max_deposit,
+            max_withdrawal,
+            epoch_withdrawal_cap,
+        )
+    }
+
+    /// Set the guardian allowed to pause the pool (admin only)
+    pub fn set_guardian(ctx: Context<SetGuardian>, new_guardian: Pubkey) -> Result<()> {
+        instructions::set_guardian::handler(ctx, new_guardian)
+    }
+
+    /// Deposit wSOL, unwrapped into a SOL note
+    pub fn deposit_wsol(
+        ctx: Context<DepositWsol>,
+        precommitment: [u8; 32],
+        encrypted_output: Vec<u8>,
         amount: u64,
-        fee: u64,
+        leaf_index: u64,
     ) -> Result<()> {
-        instructions::withdraw_spl::handler(ctx, inputs, recipient, amount, fee)
+        instructions::deposit_wsol::handler(ctx, precommitment, encrypted_output, amount, leaf_index)
+    }
+
+    /// Whitelist an AMM program for shielded swaps (admin only)
+    pub fn register_swap_program(ctx: Context<RegisterSwapProgram>) -> Result<()> {
+        instructions::register_swap_program::handler(ctx)
+    }
+
+    /// Remove an AMM program from the swap whitelist (admin only)
+    pub fn remove_swap_program(ctx: Context<RemoveSwapProgram>) -> Result<()> {
+        instructions::remove_swap_program::handler(ctx)
+    }
+
+    /// Spend a note, swap it through a whitelisted AMM and deposit the proceeds as a new note
+    pub fn swap_shielded<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SwapShielded<'info>>,
+        inputs: SwapInputs,
+        proof: Vec<u8>,
+    ) -> Result<()> {
+        instructions::swap_shielded::handler(ctx, inputs, proof)
+    }
+
+    /// Update a registered mint's withdrawal fee bps and flat minimum fee (admin only)
+    pub fn update_mint_config(
+        ctx: Context<UpdateMintConfig>,
+        fee_bps: u16,
+        min_fee: u64,
+    ) -> Result<()> {
+        instructions::update_mint_config::handler(ctx, fee_bps, min_fee)
+    }
+
+    /// Install the proposed withdrawal verifier once its time lock has passed (admin only)
+    pub fn apply_verifier_program(ctx: Context<ApplyVerifierProgram>) -> Result<()> {
+        instructions::apply_verifier_program::handler(ctx)
+    }
+
+    /// Upgrade a pool's config and trees to the current account layouts (admin only)
+    pub fn migrate(ctx: Context<Migrate>) -> Result<()> {
+        instructions::migrate::handler(ctx)
     }
 }
@@ -0,0 +1,334 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use std::mem::{offset_of, size_of};
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::state::{MerkleTree, PoolConfig, RateLimits};
+
+/// Account size of the current MerkleTree layout
+pub const MERKLE_TREE_LEN: usize = 8 + size_of::<MerkleTree>();
+
+/// Byte offsets of MerkleTree fields read before the account can be loaded
+const TREE_GENERATION_OFFSET: usize = 8 + offset_of!(MerkleTree, generation);
+const TREE_BUMP_OFFSET: usize = 8 + offset_of!(MerkleTree, bump);
+const TREE_VERSION_OFFSET: usize = 8 + offset_of!(MerkleTree, version);
+const TREE_SEQUENCE_OFFSET: usize = 8 + offset_of!(MerkleTree, sequence);
+
+/// PoolConfig layout version 1. The reserved bytes followed the variable-length
+/// denominations, so fields carved from them had no fixed offset.
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct PoolConfigV1 {
+    pub version: u8,
+    pub pool_id: u64,
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+    pub relayer: Pubkey,
+    pub fee_bps: u16,
+    pub fee_recipient: Pubkey,
+    pub verifier_program: Pubkey,
+    pub transact_verifier_program: Pubkey,
+    pub merge_verifier_program: Pubkey,
+    pub guardian: Pubkey,
+    pub paused: bool,
+    pub paused_at: i64,
+    pub bump: u8,
+    pub tree_bump: u8,
+    pub vault_bump: u8,
+    pub current_generation: u64,
+    pub nullifier_shards: u16,
+    pub sol_limits: RateLimits,
+    pub denominations: Vec<u64>,
+    pub _reserved: [u8; ACCOUNT_RESERVED_BYTES],
+}
+
+impl From<PoolConfigV1> for PoolConfig {
+    /// Version 2 fields carved from the reserved bytes start zeroed
+    fn from(v1: PoolConfigV1) -> Self {
+        PoolConfig {
+            version: POOL_CONFIG_VERSION,
+            pool_id: v1.pool_id,
+            admin: v1.admin,
+            pending_admin: v1.pending_admin,
+            relayer: v1.relayer,
+            fee_bps: v1.fee_bps,
+            fee_recipient: v1.fee_recipient,
+            verifier_program: v1.verifier_program,
+            transact_verifier_program: v1.transact_verifier_program,
+            merge_verifier_program: v1.merge_verifier_program,
+            guardian: v1.guardian,
+            paused: v1.paused,
+            paused_at: v1.paused_at,
+            bump: v1.bump,
+            tree_bump: v1.tree_bump,
+            vault_bump: v1.vault_bump,
+            current_generation: v1.current_generation,
+            nullifier_shards: v1.nullifier_shards,
+            sol_limits: v1.sol_limits,
+            config_sequence: 0,
+            unpaused_at: 0,
+            pending_verifier_program: Pubkey::default(),
+            verifier_proposed_at: 0,
+            _reserved: [0; ACCOUNT_RESERVED_BYTES - 56],
+            sol_denominations: v1.denominations,
+        }
+    }
+}
+
+/// Decode PoolConfig account data stored in the current or the previous layout,
+/// upgraded to the current one in memory. Returns the config and the version it
+/// was stored in; any other version is rejected.
+pub fn decode_pool_config(data: &[u8]) -> Result<(PoolConfig, u8)> {
+    require!(
+        data.starts_with(PoolConfig::DISCRIMINATOR) && data.len() > 8,
+        anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+    );
+    let version = data[8];
+    if version == POOL_CONFIG_VERSION {
+        return Ok((PoolConfig::try_deserialize(&mut &data[..])?, version));
+    }
+    require!(
+        version == POOL_CONFIG_VERSION - 1,
+        ZkPoolError::UnsupportedAccountVersion
+    );
+    let v1 = PoolConfigV1::deserialize(&mut &data[8..])
+        .map_err(|_| error!(anchor_lang::error::ErrorCode::AccountDidNotDeserialize))?;
+    Ok((v1.into(), version))
+}
+
+/// Read a PoolConfig in any supported layout (see `decode_pool_config`)
+pub fn read_pool_config(info: &AccountInfo) -> Result<(PoolConfig, u8)> {
+    decode_pool_config(&info.try_borrow_data()?)
+}
+
+/// Write an upgraded PoolConfig back, growing the account to the current size
+pub fn write_pool_config<'info>(
+    info: &AccountInfo<'info>,
+    config: &PoolConfig,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    grow(info, 8 + PoolConfig::INIT_SPACE, payer, system_program)?;
+    let mut data = info.try_borrow_mut_data()?;
+    // Clear bytes of the old layout that the new one may not overwrite
+    data.fill(0);
+    config.try_serialize(&mut &mut data[..])
+}
+
+/// Upgrade MerkleTree account data of the current length in place. Returns the
+/// version it was stored in; only the current and the previous version are accepted.
+pub fn upgrade_merkle_tree_data(data: &mut [u8]) -> Result<u8> {
+    let version = data[TREE_VERSION_OFFSET];
+    check_merkle_tree_version(version)?;
+    if version < MERKLE_TREE_VERSION {
+        // Version 2 carved `sequence` from the reserved bytes; clear them so it
+        // starts at 0
+        data[TREE_SEQUENCE_OFFSET..].fill(0);
+        data[TREE_VERSION_OFFSET] = MERKLE_TREE_VERSION;
+    }
+    Ok(version)
+}
+
+fn check_merkle_tree_version(version: u8) -> Result<()> {
+    require!(
+        version == MERKLE_TREE_VERSION || version == MERKLE_TREE_VERSION - 1,
+        ZkPoolError::UnsupportedAccountVersion
+    );
+    Ok(())
+}
+
+/// Upgrade a MerkleTree of `pool_config` in place. Returns the version it was stored in.
+pub fn upgrade_merkle_tree<'info>(
+    info: &AccountInfo<'info>,
+    pool_config: &Pubkey,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<u8> {
+    let (generation, version) = {
+        let data = info.try_borrow_data()?;
+        require!(
+            data.starts_with(MerkleTree::DISCRIMINATOR)
+                && data.len() > TREE_VERSION_OFFSET
+                && data.len() <= MERKLE_TREE_LEN,
+            anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+        );
+        let generation = u64::from_le_bytes(
+            data[TREE_GENERATION_OFFSET..TREE_GENERATION_OFFSET + 8]
+                .try_into()
+                .unwrap(),
+        );
+
+        // The tree must be this pool's PDA for its generation
+        let expected = Pubkey::create_program_address(
+            &[
+                MERKLE_TREE_SEED,
+                pool_config.as_ref(),
+                &generation.to_le_bytes(),
+                &[data[TREE_BUMP_OFFSET]],
+            ],
+            &crate::ID,
+        )
+        .map_err(|_| error!(anchor_lang::error::ErrorCode::ConstraintSeeds))?;
+        require_keys_eq!(
+            expected,
+            info.key(),
+            anchor_lang::error::ErrorCode::ConstraintSeeds
+        );
+
+        (generation, data[TREE_VERSION_OFFSET])
+    };
+    // Reject unknown versions before charging rent for them
+    check_merkle_tree_version(version)?;
+    if version < MERKLE_TREE_VERSION {
+        let old_len = info.data_len();
+        grow(info, MERKLE_TREE_LEN, payer, system_program)?;
+        let mut data = info.try_borrow_mut_data()?;
+        data[old_len..].fill(0);
+        upgrade_merkle_tree_data(&mut data)?;
+        msg!("Merkle tree generation {} upgraded", generation);
+    }
+
+    Ok(version)
+}
+
+/// Grow an account to `new_len`, charging any extra rent to `payer`
+fn grow<'info>(
+    info: &AccountInfo<'info>,
+    new_len: usize,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    if info.data_len() >= new_len {
+        return Ok(());
+    }
+    let required = Rent::get()?.minimum_balance(new_len);
+    let lamports = info.lamports();
+    if required > lamports {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: info.clone(),
+                },
+            ),
+            required - lamports,
+        )?;
+    }
+    info.resize(new_len)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v1_config() -> PoolConfigV1 {
+        PoolConfigV1 {
+            version: 1,
+            pool_id: 9,
+            admin: Pubkey::new_unique(),
+            pending_admin: Pubkey::default(),
+            relayer: Pubkey::new_unique(),
+            fee_bps: 30,
+            fee_recipient: Pubkey::new_unique(),
+            verifier_program: Pubkey::new_unique(),
+            transact_verifier_program: Pubkey::default(),
+            merge_verifier_program: Pubkey::default(),
+            guardian: Pubkey::new_unique(),
+            paused: true,
+            paused_at: 1_000,
+            bump: 254,
+            tree_bump: 253,
+            vault_bump: 252,
+            current_generation: 3,
+            nullifier_shards: 16,
+            sol_limits: RateLimits::unlimited(),
+            denominations: vec![100_000_000, 1_000_000_000],
+            _reserved: [0; ACCOUNT_RESERVED_BYTES],
+        }
+    }
+
+    /// Account data of a config in the old layout, padded to its allocated size
+    fn v1_buffer(config: &PoolConfigV1) -> Vec<u8> {
+        let mut data = PoolConfig::DISCRIMINATOR.to_vec();
+        config.serialize(&mut data).unwrap();
+        data.resize(8 + PoolConfig::INIT_SPACE, 0);
+        data
+    }
+
+    fn tree_buffer(version: u8) -> Vec<u8> {
+        let mut data = vec![0u8; MERKLE_TREE_LEN];
+        data[..8].copy_from_slice(MerkleTree::DISCRIMINATOR);
+        data[TREE_GENERATION_OFFSET] = 3;
+        data[TREE_VERSION_OFFSET] = version;
+        data
+    }
+
+    #[test]
+    fn pool_config_v1_buffer_upgrades() {
+        let old = v1_config();
+        let mut data = v1_buffer(&old);
+        let (config, from_version) = decode_pool_config(&data).unwrap();
+        assert_eq!(from_version, 1);
+        assert_eq!(config.version, POOL_CONFIG_VERSION);
+        assert_eq!(config.admin, old.admin);
+        assert_eq!(config.guardian, old.guardian);
+        assert_eq!(config.paused_at, 1_000);
+        assert_eq!(config.vault_bump, 252);
+        assert_eq!(config.current_generation, 3);
+        assert_eq!(config.nullifier_shards, 16);
+        assert_eq!(config.sol_denominations, old.denominations);
+        assert_eq!(config.config_sequence, 0);
+        assert_eq!(config.unpaused_at, 0);
+        assert_eq!(config.pending_verifier_program, Pubkey::default());
+
+        // Rewritten in place as `write_pool_config` does, it loads as the current layout
+        data.fill(0);
+        config.try_serialize(&mut &mut data[..]).unwrap();
+        let (reloaded, from_version) = decode_pool_config(&data).unwrap();
+        assert_eq!(from_version, POOL_CONFIG_VERSION);
+        assert_eq!(reloaded.sol_denominations, old.denominations);
+        assert_eq!(reloaded.admin, old.admin);
+    }
+
+    #[test]
+    fn pool_config_rejects_unknown_versions() {
+        for version in [0, POOL_CONFIG_VERSION + 1, u8::MAX] {
+            let mut data = v1_buffer(&v1_config());
+            data[8] = version;
+            assert!(decode_pool_config(&data).is_err());
+        }
+        let mut data = v1_buffer(&v1_config());
+        data[0] ^= 1;
+        assert!(decode_pool_config(&data).is_err());
+    }
+
+    #[test]
+    fn merkle_tree_v1_buffer_upgrades() {
+        let mut data = tree_buffer(1);
+        // Stale bytes in the old reserved space must not become the sequence
+        data[TREE_SEQUENCE_OFFSET] = 7;
+        assert_eq!(upgrade_merkle_tree_data(&mut data).unwrap(), 1);
+        assert_eq!(data[TREE_VERSION_OFFSET], MERKLE_TREE_VERSION);
+        assert!(data[TREE_SEQUENCE_OFFSET..].iter().all(|b| *b == 0));
+        assert_eq!(data[TREE_GENERATION_OFFSET], 3);
+
+        // A current tree is left unchanged
+        data[TREE_SEQUENCE_OFFSET] = 7;
+        let current = data.clone();
+        assert_eq!(
+            upgrade_merkle_tree_data(&mut data).unwrap(),
+            MERKLE_TREE_VERSION
+        );
+        assert_eq!(data, current);
+    }
+
+    #[test]
+    fn merkle_tree_rejects_unknown_versions() {
+        for version in [0, MERKLE_TREE_VERSION + 1, u8::MAX] {
+            let mut data = tree_buffer(version);
+            assert!(upgrade_merkle_tree_data(&mut data).is_err());
+            assert_eq!(data, tree_buffer(version));
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+use crate::events::{CommitmentInserted, EventHeader, RootUpdated};
+use crate::state::{CommitmentRecord, MerkleTree};
+
+/// A note commitment to append to the tree, with the fields published for indexing
+pub struct Note {
+    pub commitment: [u8; 32],
+    pub encrypted_output: Vec<u8>,
+    /// Published amount; None when hidden (change, transfers, denominated assets)
+    pub amount: Option<u64>,
+    /// None for SOL, Some(mint) for SPL tokens
+    pub mint: Option<Pubkey>,
+}
+
+/// Append a note to the tree and record it like a deposit, so wallets can
+/// recover it from chain state, then emit `CommitmentInserted`. Callers emit
+/// `RootUpdated` via `emit_root_updated` once all notes are inserted.
+pub fn insert_note(
+    pool_id: u64,
+    tree: &mut MerkleTree,
+    record: &mut CommitmentRecord,
+    depositor: Pubkey,
+    bump: u8,
+    note: Note,
+) -> Result<u64> {
+    let index = tree.insert(&note.commitment)?;
+
+    record.depositor = depositor;
+    record.generation = tree.generation;
+    record.index = index;
+    record.commitment = note.commitment;
+    record.bump = bump;
+
+    emit!(CommitmentInserted {
+        header: EventHeader::tree(pool_id, tree)?,
+        index,
+        commitment: note.commitment,
+        encrypted_output: note.encrypted_output,
+        amount: note.amount,
+        mint: note.mint,
+    });
+
+    Ok(index)
+}
+
+/// Emit the tree's current root for relayer indexing
+pub fn emit_root_updated(pool_id: u64, tree: &mut MerkleTree) -> Result<()> {
+    emit!(RootUpdated {
+        header: EventHeader::tree(pool_id, tree)?,
+        new_root: tree.current_root,
+        root_index: tree.root_history_index,
+        leaf_count: tree.next_index,
+    });
+    Ok(())
+}
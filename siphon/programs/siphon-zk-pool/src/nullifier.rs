@@ -0,0 +1,174 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use std::cmp::Ordering;
+use crate::errors::ZkPoolError;
+use crate::state::{NullifierAccount, NullifierShard, PoolConfig};
+use crate::verifier::FIELD_SIZE;
+
+/// Byte offset of the first nullifier in a shard account (discriminator + header)
+pub const SHARD_ENTRIES_OFFSET: usize = 8 + std::mem::size_of::<NullifierShard>();
+
+/// Binary search a sorted run of 32-byte nullifiers
+fn search(entries: &[u8], nullifier_hash: &[u8; 32]) -> std::result::Result<usize, usize> {
+    let (mut lo, mut hi) = (0, entries.len() / FIELD_SIZE);
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        match entries[mid * FIELD_SIZE..(mid + 1) * FIELD_SIZE].cmp(&nullifier_hash[..]) {
+            Ordering::Less => lo = mid + 1,
+            Ordering::Greater => hi = mid,
+            Ordering::Equal => return Ok(mid),
+        }
+    }
+    Err(lo)
+}
+
+/// Insert a nullifier at entry `pos` of a sorted run of `count` entries,
+/// shifting the tail up; `entries` must have room for `count + 1` entries
+fn insert_at(entries: &mut [u8], count: usize, pos: usize, nullifier_hash: &[u8; 32]) {
+    let at = pos * FIELD_SIZE;
+    entries.copy_within(at..count * FIELD_SIZE, at + FIELD_SIZE);
+    entries[at..at + FIELD_SIZE].copy_from_slice(nullifier_hash);
+}
+
+/// Insert a nullifier into a shard, growing the account by one entry and
+/// charging the extra rent to `payer`. Entries are kept sorted so lookups are
+/// a binary search; fails with `NullifierAlreadySpent` if already present.
+pub fn insert_into_shard<'info>(
+    shard: &AccountLoader<'info, NullifierShard>,
+    nullifier_hash: &[u8; 32],
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let count = shard.load()?.count as usize;
+    let info = shard.to_account_info();
+    let used = count * FIELD_SIZE;
+
+    let pos = {
+        let data = info.try_borrow_data()?;
+        let entries = &data[SHARD_ENTRIES_OFFSET..SHARD_ENTRIES_OFFSET + used];
+        match search(entries, nullifier_hash) {
+            Ok(_) => return err!(ZkPoolError::NullifierAlreadySpent),
+            Err(pos) => pos,
+        }
+    };
+
+    // Grow by one entry; the payer tops up rent for the extra 32 bytes
+    let new_len = SHARD_ENTRIES_OFFSET + used + FIELD_SIZE;
+    let required = Rent::get()?.minimum_balance(new_len);
+    let lamports = info.lamports();
+    if required > lamports {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: info.clone(),
+                },
+            ),
+            required - lamports,
+        )?;
+    }
+    info.resize(new_len)?;
+
+    {
+        let mut data = info.try_borrow_mut_data()?;
+        insert_at(&mut data[SHARD_ENTRIES_OFFSET..new_len], count, pos, nullifier_hash);
+    }
+
+    shard.load_mut()?.count = (count + 1) as u64;
+    Ok(())
+}
+
+/// Mark a nullifier spent in the store the pool uses: a freshly created
+/// NullifierAccount PDA (per-nullifier mode) or an insert into its shard.
+pub fn mark_spent<'info>(
+    pool_config: &PoolConfig,
+    nullifier_account: Option<&mut Account<'info, NullifierAccount>>,
+    nullifier_bump: Option<u8>,
+    nullifier_shard: Option<&AccountLoader<'info, NullifierShard>>,
+    nullifier_hash: &[u8; 32],
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    match (pool_config.uses_nullifier_shards(), nullifier_account, nullifier_shard) {
+        (false, Some(nullifier), None) => {
+            nullifier.nullifier_hash = *nullifier_hash;
+            nullifier.bump = nullifier_bump.ok_or(ZkPoolError::InvalidNullifierAccount)?;
+            Ok(())
+        }
+        (true, None, Some(shard)) => insert_into_shard(shard, nullifier_hash, payer, system_program),
+        _ => err!(ZkPoolError::InvalidNullifierAccount),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hash(first: u8, last: u8) -> [u8; 32] {
+        let mut hash = [0u8; 32];
+        hash[0] = first;
+        hash[31] = last;
+        hash
+    }
+
+    /// Insert like `insert_into_shard`, growing a plain buffer instead of an account
+    fn insert(entries: &mut Vec<u8>, nullifier_hash: &[u8; 32]) -> bool {
+        let count = entries.len() / FIELD_SIZE;
+        match search(entries, nullifier_hash) {
+            Ok(_) => false,
+            Err(pos) => {
+                entries.resize(entries.len() + FIELD_SIZE, 0);
+                insert_at(entries, count, pos, nullifier_hash);
+                true
+            }
+        }
+    }
+
+    #[test]
+    fn search_empty() {
+        assert_eq!(search(&[], &hash(1, 1)), Err(0));
+    }
+
+    #[test]
+    fn inserts_keep_entries_sorted() {
+        let hashes = [hash(5, 0), hash(1, 9), hash(9, 1), hash(1, 2), hash(0, 0), hash(5, 1)];
+        let mut entries = Vec::new();
+        for h in &hashes {
+            assert!(insert(&mut entries, h));
+        }
+
+        let mut sorted = hashes.to_vec();
+        sorted.sort();
+        let stored: Vec<&[u8]> = entries.chunks(FIELD_SIZE).collect();
+        assert_eq!(stored.len(), sorted.len());
+        for (i, h) in sorted.iter().enumerate() {
+            assert_eq!(stored[i], &h[..]);
+            assert_eq!(search(&entries, h), Ok(i));
+        }
+    }
+
+    #[test]
+    fn duplicate_is_detected() {
+        let mut entries = Vec::new();
+        for h in [hash(3, 0), hash(1, 0), hash(2, 0)] {
+            assert!(insert(&mut entries, &h));
+        }
+        for h in [hash(1, 0), hash(2, 0), hash(3, 0)] {
+            assert!(!insert(&mut entries, &h));
+        }
+        assert_eq!(entries.len(), 3 * FIELD_SIZE);
+    }
+
+    #[test]
+    fn search_reports_insertion_point() {
+        let mut entries = Vec::new();
+        for h in [hash(2, 0), hash(4, 0), hash(6, 0)] {
+            insert(&mut entries, &h);
+        }
+        assert_eq!(search(&entries, &hash(1, 0)), Err(0));
+        assert_eq!(search(&entries, &hash(3, 0)), Err(1));
+        assert_eq!(search(&entries, &hash(5, 0)), Err(2));
+        assert_eq!(search(&entries, &hash(7, 0)), Err(3));
+    }
+}
@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+use solana_poseidon::{hashv, Endianness, Parameters};
+use crate::errors::ZkPoolError;
+use crate::verifier::u64_to_field;
+
+/// Poseidon BN254 hash of two field elements (big-endian), matching
+/// `poseidon::bn254::hash_2` in the Noir circuit. Uses the sol_poseidon syscall on-chain.
+pub fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> Result<[u8; 32]> {
+    let hash = hashv(Parameters::Bn254X5, Endianness::BigEndian, &[left, right])
+        .map_err(|_| ZkPoolError::InvalidFieldElement)?;
+    Ok(hash.to_bytes())
+}
+
+/// Note commitment Poseidon(value, asset_id, precommitment), where
+/// precommitment = Poseidon(nullifier, secret) is supplied by the depositor.
+/// Computed on-chain so the leaf always matches the amount and mint deposited.
+pub fn commitment(value: u64, asset_id: &[u8; 32], precommitment: &[u8; 32]) -> Result<[u8; 32]> {
+    let value = u64_to_field(value);
+    let hash = hashv(
+        Parameters::Bn254X5,
+        Endianness::BigEndian,
+        &[&value, asset_id, precommitment],
+    )
+    .map_err(|_| ZkPoolError::InvalidFieldElement)?;
+    Ok(hash.to_bytes())
+}
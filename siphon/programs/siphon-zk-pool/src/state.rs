@@ -1,79 +1,382 @@
 use anchor_lang::prelude::*;
+use crate::constants::*;
+use crate::errors::ZkPoolError;
+use crate::poseidon;
 
-/// Global pool configuration
+/// Pool configuration. Each pool is identified by `pool_id`; its tree, vault,
+/// nullifiers, commitment records and mint configs are PDAs keyed by this account.
 #[account]
 #[derive(InitSpace)]
 pub struct PoolConfig {
+    /// Layout version (POOL_CONFIG_VERSION); upgraded in place by `migrate`
+    pub version: u8,
+    /// Pool identifier (PDA seed)
+    pub pool_id: u64,
     /// Admin authority (can update config, pause)
     pub admin: Pubkey,
-    /// Relayer authority (can submit withdrawals, update roots)
+    /// Proposed admin awaiting `accept_admin` (default when none)
+    pub pending_admin: Pubkey,
+    /// Operator relayer; withdrawals it submits must pay fees to `fee_recipient`
     pub relayer: Pubkey,
     /// Withdrawal fee in basis points
     pub fee_bps: u16,
     /// Account that receives fees
     pub fee_recipient: Pubkey,
-    /// Sunspot verifier program ID (for future on-chain proof verification)
+    /// Sunspot verifier program ID (withdrawal proofs are verified via CPI)
     pub verifier_program: Pubkey,
+    /// Sunspot verifier for the join-split (`transact`) circuit; default disables transfers
+    pub transact_verifier_program: Pubkey,
+    /// Sunspot verifier for the N-input `merge` circuit; default disables merging
+    pub merge_verifier_program: Pubkey,
+    /// Guardian that can pause (but not unpause) the pool; default when none
+    pub guardian: Pubkey,
     /// Whether the pool is paused
     pub paused: bool,
+    /// Unix timestamp at which the current pause started
+    pub paused_at: i64,
     /// PDA bump
     pub bump: u8,
-    /// Merkle tree account bump (cached for easy access)
+    /// Active Merkle tree account bump (cached for easy access)
     pub tree_bump: u8,
     /// Pool vault bump (cached for easy access)
     pub vault_bump: u8,
+    /// Generation of the active Merkle tree; bumped by `rollover_tree`
+    pub current_generation: u64,
+    /// Number of nullifier shards (0 = one NullifierAccount PDA per nullifier).
+    /// Fixed at pool creation.
+    pub nullifier_shards: u16,
+    /// Deposit/withdrawal limits for SOL
+    pub sol_limits: RateLimits,
+    /// Number of config events emitted for this pool (see `EventHeader`); taken
+    /// from the reserved space
+    pub config_sequence: u64,
+    /// Unix timestamp of the last unpause (0 if never); taken from the reserved space
+    pub unpaused_at: i64,
+    /// Withdrawal verifier awaiting `apply_verifier_program` (default when none);
+    /// taken from the reserved space
+    pub pending_verifier_program: Pubkey,
+    /// Unix timestamp at which `pending_verifier_program` was proposed
+    pub verifier_proposed_at: i64,
+    /// Reserved for future fields (zeroed; ACCOUNT_RESERVED_BYTES minus the fields
+    /// above taken from it). Kept ahead of the variable-length denominations so
+    /// fields carved from it have fixed offsets.
+    pub _reserved: [u8; ACCOUNT_RESERVED_BYTES - 56],
+    /// Fixed SOL deposit denominations in lamports (empty = any amount).
+    /// When set, SOL deposit amounts are omitted from events.
+    #[max_len(MAX_DENOMINATIONS)]
+    pub sol_denominations: Vec<u64>,
 }
 
-/// Merkle tree state — stores root history and next leaf index.
-/// The full tree is maintained off-chain by the relayer using Poseidon BN254.
-/// On-chain we only store roots (for withdrawal verification) and the leaf counter.
+impl PoolConfig {
+    /// Return the sequence number for the next config event and advance the counter
+    pub fn next_config_sequence(&mut self) -> u64 {
+        let sequence = self.config_sequence;
+        self.config_sequence += 1;
+        sequence
+    }
+
+    /// Pause or unpause at `now`. A pause that follows an unpause by less than
+    /// ESCAPE_HATCH_DELAY_SECONDS keeps the earlier pause's start, so unpausing and
+    /// re-pausing cannot push back the escape hatch. Pausing cancels a pending
+    /// verifier change.
+    pub fn set_paused(&mut self, paused: bool, now: i64) {
+        if paused && !self.paused {
+            if self.unpaused_at == 0
+                || now.saturating_sub(self.unpaused_at) >= ESCAPE_HATCH_DELAY_SECONDS
+            {
+                self.paused_at = now;
+            }
+            self.pending_verifier_program = Pubkey::default();
+        } else if !paused && self.paused {
+            self.unpaused_at = now;
+        }
+        self.paused = paused;
+    }
+
+    /// Whether the escape hatch is open: the pool has been paused for at least
+    /// ESCAPE_HATCH_DELAY_SECONDS. Users then exit without the operator, so
+    /// operator-set withdrawal limits no longer apply.
+    pub fn escape_hatch_open(&self, now: i64) -> bool {
+        self.paused && now.saturating_sub(self.paused_at) >= ESCAPE_HATCH_DELAY_SECONDS
+    }
+
+    /// Whether withdrawals are accepted: the pool is live or its escape hatch is open
+    pub fn withdrawals_open(&self, now: i64) -> bool {
+        !self.paused || self.escape_hatch_open(now)
+    }
+
+    /// Propose a new withdrawal verifier at `now`. `apply_verifier` installs it no
+    /// sooner than ESCAPE_HATCH_DELAY_SECONDS later, and a pause in between cancels
+    /// it. Users can therefore exit under the current verifier before a change lands,
+    /// and the escape hatch always runs on a verifier that was live that long.
+    pub fn propose_verifier(&mut self, verifier_program: Pubkey, now: i64) -> Result<()> {
+        require!(!self.paused, ZkPoolError::ConfigFrozenWhilePaused);
+        require!(
+            verifier_program != Pubkey::default(),
+            ZkPoolError::InvalidVerifierProgram
+        );
+        self.pending_verifier_program = verifier_program;
+        self.verifier_proposed_at = now;
+        Ok(())
+    }
+
+    /// Install the pending withdrawal verifier once its time lock has passed.
+    /// Returns the verifier it replaces.
+    pub fn apply_verifier(&mut self, now: i64) -> Result<Pubkey> {
+        require!(!self.paused, ZkPoolError::ConfigFrozenWhilePaused);
+        require!(
+            self.pending_verifier_program != Pubkey::default()
+                && now.saturating_sub(self.verifier_proposed_at) >= ESCAPE_HATCH_DELAY_SECONDS,
+            ZkPoolError::VerifierChangeNotReady
+        );
+        let old_verifier = self.verifier_program;
+        self.verifier_program = self.pending_verifier_program;
+        self.pending_verifier_program = Pubkey::default();
+        Ok(old_verifier)
+    }
+
+    /// Whether SOL deposits are restricted to fixed denominations
+    pub fn is_denominated(&self) -> bool {
+        !self.sol_denominations.is_empty()
+    }
+
+    /// Check a SOL deposit amount against the configured denominations
+    pub fn is_allowed_deposit(&self, amount: u64) -> bool {
+        is_allowed_denomination(&self.sol_denominations, amount)
+    }
+
+    /// Amount to publish for a SOL deposit; denominated assets keep amounts
+    /// out of events and logs
+    pub fn deposit_event_amount(&self, amount: u64) -> Option<u64> {
+        (!self.is_denominated()).then_some(amount)
+    }
+
+    /// Submissions by the pool relayer must route fees to the pool's fee
+    /// recipient; anyone else relaying may collect the fee bound into the proof
+    pub fn check_fee_recipient(&self, submitter: &Pubkey, fee_recipient: &Pubkey) -> Result<()> {
+        if *submitter == self.relayer {
+            require_keys_eq!(*fee_recipient, self.fee_recipient, ZkPoolError::InvalidFeeConfig);
+        }
+        Ok(())
+    }
+
+    /// Whether spent nullifiers are stored in shards rather than one PDA each
+    pub fn uses_nullifier_shards(&self) -> bool {
+        self.nullifier_shards > 0
+    }
+
+    /// Shard holding a nullifier, keyed by its low 16 bits
+    pub fn nullifier_shard_index(&self, nullifier_hash: &[u8; 32]) -> u16 {
+        let key = u16::from_be_bytes([nullifier_hash[30], nullifier_hash[31]]);
+        key % self.nullifier_shards.max(1)
+    }
+
+    /// Maximum fee that may be charged on a withdrawal of gross `amount` (fee included)
+    pub fn max_fee(&self, amount: u64) -> Result<u64> {
+        bps_of(amount, self.fee_bps)
+    }
+
+    /// Check a withdrawal fee against the cap on the gross `amount + fee`
+    pub fn check_fee(&self, amount: u64, fee: u64) -> Result<()> {
+        require!(
+            fee <= self.max_fee(gross_of(amount, fee)?)?,
+            ZkPoolError::InvalidFeeConfig
+        );
+        Ok(())
+    }
+}
+
+/// Gross withdrawal leaving the pool: what the recipient gets plus the fee
+fn gross_of(amount: u64, fee: u64) -> Result<u64> {
+    amount
+        .checked_add(fee)
+        .ok_or_else(|| error!(ZkPoolError::Overflow))
+}
+
+/// `bps` basis points of `amount`, rounded down
+fn bps_of(amount: u64, bps: u16) -> Result<u64> {
+    let fee = (amount as u128)
+        .checked_mul(bps as u128)
+        .ok_or(ZkPoolError::Overflow)?
+        / BPS_DENOMINATOR as u128;
+    u64::try_from(fee).map_err(|_| error!(ZkPoolError::Overflow))
+}
+
+/// Validate a denomination list: at most MAX_DENOMINATIONS non-zero amounts in
+/// strictly ascending order (empty disables denominations)
+pub fn check_denominations(denominations: &[u64]) -> Result<()> {
+    require!(
+        denominations.len() <= MAX_DENOMINATIONS,
+        ZkPoolError::InvalidDenomination
+    );
+    require!(
+        denominations.iter().all(|d| *d > 0),
+        ZkPoolError::InvalidDenomination
+    );
+    require!(
+        denominations.windows(2).all(|w| w[0] < w[1]),
+        ZkPoolError::InvalidDenomination
+    );
+    Ok(())
+}
+
+/// Denominations only gate deposits: withdrawals and change notes may carry any
+/// amount, so changing the list never strands existing notes.
+fn is_allowed_denomination(denominations: &[u64], amount: u64) -> bool {
+    denominations.is_empty() || denominations.contains(&amount)
+}
+
+/// Per-asset amount limits and withdrawal circuit breaker. Withdrawals are
+/// summed per Solana epoch and rejected once `epoch_withdrawal_cap` is reached.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct RateLimits {
+    /// Minimum deposit amount (base units)
+    pub min_deposit: u64,
+    /// Maximum deposit amount (base units)
+    pub max_deposit: u64,
+    /// Maximum withdrawal per transaction, fee included (base units)
+    pub max_withdrawal: u64,
+    /// Maximum total withdrawn per epoch, fees included (base units)
+    pub epoch_withdrawal_cap: u64,
+    /// Epoch that `epoch_withdrawn` refers to
+    pub epoch: u64,
+    /// Total withdrawn so far in `epoch`
+    pub epoch_withdrawn: u64,
+}
+
+impl RateLimits {
+    /// Limits that accept any amount
+    pub fn unlimited() -> Self {
+        Self {
+            min_deposit: 0,
+            max_deposit: u64::MAX,
+            max_withdrawal: u64::MAX,
+            epoch_withdrawal_cap: u64::MAX,
+            epoch: 0,
+            epoch_withdrawn: 0,
+        }
+    }
+
+    /// Check a deposit amount against the per-transaction range
+    pub fn check_deposit(&self, amount: u64) -> Result<()> {
+        require!(
+            amount >= self.min_deposit && amount <= self.max_deposit,
+            ZkPoolError::LimitExceeded
+        );
+        Ok(())
+    }
+
+    /// Replace the admin-set bounds; the current epoch's withdrawn total is kept, so
+    /// lowering the cap takes effect immediately
+    pub fn update(
+        &mut self,
+        min_deposit: u64,
+        max_deposit: u64,
+        max_withdrawal: u64,
+        epoch_withdrawal_cap: u64,
+    ) -> Result<()> {
+        require!(min_deposit <= max_deposit, ZkPoolError::InvalidAmount);
+        self.min_deposit = min_deposit;
+        self.max_deposit = max_deposit;
+        self.max_withdrawal = max_withdrawal;
+        self.epoch_withdrawal_cap = epoch_withdrawal_cap;
+        Ok(())
+    }
+
+    /// Check a withdrawal against the per-transaction and per-epoch caps and record it
+    pub fn record_withdrawal(&mut self, amount: u64, epoch: u64) -> Result<()> {
+        require!(amount <= self.max_withdrawal, ZkPoolError::LimitExceeded);
+        if epoch != self.epoch {
+            self.epoch = epoch;
+            self.epoch_withdrawn = 0;
+        }
+        let total = self
+            .epoch_withdrawn
+            .checked_add(amount)
+            .ok_or(ZkPoolError::Overflow)?;
+        require!(total <= self.epoch_withdrawal_cap, ZkPoolError::LimitExceeded);
+        self.epoch_withdrawn = total;
+        Ok(())
+    }
+}
+
+/// Merkle tree state — incremental Poseidon BN254 tree (Tornado-style).
+/// Only the rightmost filled subtree per level is stored, so every leaf
+/// insertion recomputes the root on-chain in O(height) hashes.
+/// The full tree (all leaves) is reconstructed off-chain from events.
+/// Each pool owns a sequence of trees indexed by generation; once the active
+/// tree fills, `rollover_tree` opens the next one and older trees stay live
+/// for withdrawals.
 ///
 /// Uses zero_copy for efficient large account access.
 #[account(zero_copy)]
 #[repr(C)]
 pub struct MerkleTree {
-    /// Authority that can insert leaves and update roots
+    /// Pool relayer when the tree was created. Informational only: leaves are
+    /// inserted and roots derived by the program, so this key controls nothing
     pub authority: Pubkey,
+    /// Tree generation (PDA seed)
+    pub generation: u64,
     /// Next available leaf index
     pub next_index: u64,
     /// Current Merkle root
     pub current_root: [u8; 32],
-    /// Ring buffer of recent roots — flattened to [u8; 1024] (32 entries × 32 bytes)
-    /// because bytemuck only implements Pod/Zeroable for arrays up to 1024.
-    /// Access via get_root_history / set_root_history helpers.
-    pub root_history: [u8; 1024],
-    /// Current position in root_history ring buffer
+    /// Ring buffer of recent roots; only the first root_history_size entries are used
+    pub root_history: [[u8; 32]; ROOT_HISTORY_SIZE],
+    /// Total number of roots pushed into the ring buffer
     pub root_history_index: u64,
     /// Tree height (determines max capacity: 2^height leaves)
     pub height: u8,
     /// PDA bump
     pub bump: u8,
+    /// Number of ring buffer slots in use (1..=ROOT_HISTORY_SIZE)
+    pub root_history_size: u16,
+    /// Layout version (MERKLE_TREE_VERSION); upgraded in place by `migrate`
+    pub version: u8,
     /// Padding for alignment
-    pub _padding: [u8; 6],
+    pub _padding: [u8; 3],
+    /// Last left-hand node inserted at each level
+    pub filled_subtrees: [[u8; 32]; MERKLE_TREE_HEIGHT as usize],
+    /// Roots of empty subtrees: zeros[0] = 0, zeros[i + 1] = H(zeros[i], zeros[i])
+    pub zeros: [[u8; 32]; MERKLE_TREE_HEIGHT as usize + 1],
+    /// Number of events emitted for this tree (see `EventHeader`); taken from the
+    /// reserved space, so it starts at 0 in upgraded trees
+    pub sequence: u64,
+    /// Reserved for future fields (zeroed; ACCOUNT_RESERVED_BYTES minus `sequence`)
+    pub _reserved: [u64; (ACCOUNT_RESERVED_BYTES - 8) / 8],
 }
 
 impl MerkleTree {
-    /// Get the i-th root from the history ring buffer
-    pub fn get_root_history(&self, i: usize) -> [u8; 32] {
-        let offset = i * 32;
-        let mut root = [0u8; 32];
-        root.copy_from_slice(&self.root_history[offset..offset + 32]);
-        root
+    /// Return the sequence number for the next event and advance the counter
+    pub fn next_sequence(&mut self) -> u64 {
+        let sequence = self.sequence;
+        self.sequence += 1;
+        sequence
     }
 
-    /// Set the i-th root in the history ring buffer
-    pub fn set_root_history(&mut self, i: usize, root: &[u8; 32]) {
-        let offset = i * 32;
-        self.root_history[offset..offset + 32].copy_from_slice(root);
+    /// Number of filled entries in the root history ring buffer
+    pub fn root_history_len(&self) -> usize {
+        let size = self.root_history_size as usize;
+        self.root_history_index.min(size as u64) as usize
     }
 
-    /// Check if a root exists in the root history
+    /// Check if a root is the current root or one of the filled history entries.
+    /// The all-zero root is never accepted. Searches newest to oldest.
     pub fn is_known_root(&self, root: &[u8; 32]) -> bool {
+        if *root == [0u8; 32] {
+            return false;
+        }
         if self.current_root == *root {
             return true;
         }
-        for i in 0..32 {
-            if self.get_root_history(i) == *root {
+        let size = self.root_history_size as usize;
+        if size == 0 {
+            return false;
+        }
+        let newest = (self.root_history_index % size as u64) as usize;
+        for i in 1..=self.root_history_len() {
+            let idx = (newest + size - i) % size;
+            if self.root_history[idx] == *root {
                 return true;
             }
         }
@@ -84,6 +387,45 @@ impl MerkleTree {
     pub fn is_full(&self) -> bool {
         self.next_index >= (1u64 << self.height as u64)
     }
+
+    /// Compute empty-subtree hashes and set the root to the empty tree root
+    pub fn init_zeros(&mut self) -> Result<()> {
+        let height = self.height as usize;
+        self.zeros[0] = [0u8; 32];
+        for i in 0..height {
+            self.zeros[i + 1] = poseidon::hash_pair(&self.zeros[i], &self.zeros[i])?;
+            self.filled_subtrees[i] = self.zeros[i];
+        }
+        self.current_root = self.zeros[height];
+        Ok(())
+    }
+
+    /// Insert a leaf, push the previous root into history and return the leaf index
+    pub fn insert(&mut self, leaf: &[u8; 32]) -> Result<u64> {
+        require!(!self.is_full(), ZkPoolError::TreeFull);
+
+        let index = self.next_index;
+        let mut current_index = index;
+        let mut current = *leaf;
+        for i in 0..self.height as usize {
+            let (left, right) = if current_index % 2 == 0 {
+                self.filled_subtrees[i] = current;
+                (current, self.zeros[i])
+            } else {
+                (self.filled_subtrees[i], current)
+            };
+            current = poseidon::hash_pair(&left, &right)?;
+            current_index /= 2;
+        }
+
+        let idx = (self.root_history_index % self.root_history_size as u64) as usize;
+        self.root_history[idx] = self.current_root;
+        self.root_history_index = self.root_history_index.wrapping_add(1);
+        self.current_root = current;
+        self.next_index = index.checked_add(1).ok_or(ZkPoolError::Overflow)?;
+
+        Ok(index)
+    }
 }
 
 /// Nullifier account — existence proves this nullifier has been spent.
@@ -97,15 +439,617 @@ pub struct NullifierAccount {
     pub bump: u8,
 }
 
-/// Commitment record — stores each deposit's commitment on-chain
-/// for queryability by the relayer and clients.
+/// Nullifier shard — compact alternative to one NullifierAccount per nullifier.
+/// The header is followed by `count` sorted 32-byte nullifier hashes; the
+/// account grows by 32 bytes per spend (see `nullifier::insert_into_shard`).
+#[account(zero_copy)]
+#[repr(C)]
+pub struct NullifierShard {
+    /// Pool this shard belongs to
+    pub pool: Pubkey,
+    /// Number of nullifiers stored after the header
+    pub count: u64,
+    /// Shard index (PDA seed)
+    pub shard: u16,
+    /// PDA bump
+    pub bump: u8,
+    /// Padding for alignment
+    pub _padding: [u8; 5],
+}
+
+/// Commitment record — stores each deposit's (and change output's) commitment on-chain
+/// for queryability by the relayer and clients. Not needed by the program
+/// itself, so it can be closed via `close_commitment_record` to refund rent.
 #[account]
 #[derive(InitSpace)]
 pub struct CommitmentRecord {
+    /// Account that paid rent, refunded on close (the withdrawal submitter for change outputs)
+    pub depositor: Pubkey,
+    /// Generation of the tree holding the leaf
+    pub generation: u64,
     /// Leaf index in the Merkle tree
     pub index: u64,
-    /// The commitment hash (Poseidon(value, Poseidon(nullifier, secret)))
+    /// The commitment hash (Poseidon(value, asset_id, Poseidon(nullifier, secret)))
     pub commitment: [u8; 32],
     /// PDA bump
     pub bump: u8,
 }
+
+impl CommitmentRecord {
+    /// The depositor may close its record at any time. Anyone else must wait until
+    /// the record's tree has been rolled over, so records that note recovery reads
+    /// for the active tree cannot be deleted by a third party.
+    pub fn can_close(&self, closer: &Pubkey, current_generation: u64) -> bool {
+        *closer == self.depositor || self.generation < current_generation
+    }
+}
+
+/// Per-mint configuration — a mint must be registered (allow-listed) by the
+/// admin before it can be deposited.
+#[account]
+#[derive(InitSpace)]
+pub struct MintConfig {
+    /// SPL token mint this config applies to
+    pub mint: Pubkey,
+    /// Whether deposits are accepted (withdrawals stay open when disabled)
+    pub enabled: bool,
+    /// Deposit/withdrawal limits for this mint
+    pub limits: RateLimits,
+    /// Mint decimals (cached at registration)
+    pub decimals: u8,
+    /// Withdrawal fee in basis points for this mint
+    pub fee_bps: u16,
+    /// Flat minimum fee a relayer may charge regardless of fee_bps (base units)
+    pub min_fee: u64,
+    /// PDA bump
+    pub bump: u8,
+    /// Fixed deposit denominations in base units (empty = any amount).
+    /// When set, deposit amounts of this mint are omitted from events.
+    #[max_len(MAX_DENOMINATIONS)]
+    pub denominations: Vec<u64>,
+}
+
+impl MintConfig {
+    /// Whether deposits of this mint are restricted to fixed denominations
+    pub fn is_denominated(&self) -> bool {
+        !self.denominations.is_empty()
+    }
+
+    /// Check a deposit amount against this mint's denominations
+    pub fn is_allowed_deposit(&self, amount: u64) -> bool {
+        is_allowed_denomination(&self.denominations, amount)
+    }
+
+    /// Amount to publish for a deposit of this mint (None when denominated)
+    pub fn deposit_event_amount(&self, amount: u64) -> Option<u64> {
+        (!self.is_denominated()).then_some(amount)
+    }
+
+    /// Maximum fee that may be charged on a withdrawal of gross `amount` (fee
+    /// included): `fee_bps` of it, raised to `min_fee` but never past MAX_FEE_BPS
+    /// of the amount
+    pub fn max_fee(&self, amount: u64) -> Result<u64> {
+        let fee = bps_of(amount, self.fee_bps)?;
+        let ceiling = bps_of(amount, MAX_FEE_BPS)?;
+        Ok(fee.max(self.min_fee.min(ceiling)))
+    }
+
+    /// Check a withdrawal fee against the cap on the gross `amount + fee`
+    pub fn check_fee(&self, amount: u64, fee: u64) -> Result<()> {
+        require!(
+            fee <= self.max_fee(gross_of(amount, fee)?)?,
+            ZkPoolError::InvalidFeeConfig
+        );
+        Ok(())
+    }
+
+    /// Validate a mint's fees: `fee_bps` within MAX_FEE_BPS, and a flat `min_fee`
+    /// no larger than MAX_FEE_BPS of the smallest deposit
+    pub fn check_fee_config(min_deposit: u64, fee_bps: u16, min_fee: u64) -> Result<()> {
+        require!(fee_bps <= MAX_FEE_BPS, ZkPoolError::InvalidFeeConfig);
+        require!(
+            min_fee <= bps_of(min_deposit, MAX_FEE_BPS)?,
+            ZkPoolError::InvalidFeeConfig
+        );
+        Ok(())
+    }
+}
+
+/// Allow-list entry for an AMM program `swap_shielded` may CPI into. The entry
+/// existing is the permission; the admin removes it by closing the account.
+#[account]
+#[derive(InitSpace)]
+pub struct SwapProgramConfig {
+    /// Whitelisted AMM program
+    pub program: Pubkey,
+    /// PDA bump
+    pub bump: u8,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::__private::bytemuck::Zeroable;
+
+    fn new_tree(root_history_size: u16) -> Box<MerkleTree> {
+        let mut tree = Box::new(MerkleTree::zeroed());
+        tree.height = MERKLE_TREE_HEIGHT;
+        tree.root_history_size = root_history_size;
+        tree.init_zeros().unwrap();
+        tree
+    }
+
+    fn leaf(i: u8) -> [u8; 32] {
+        let mut leaf = [0u8; 32];
+        leaf[31] = i + 1;
+        leaf
+    }
+
+    fn pool(fee_bps: u16) -> PoolConfig {
+        PoolConfig {
+            version: POOL_CONFIG_VERSION,
+            pool_id: 0,
+            admin: Pubkey::default(),
+            pending_admin: Pubkey::default(),
+            relayer: Pubkey::default(),
+            fee_bps,
+            fee_recipient: Pubkey::default(),
+            verifier_program: Pubkey::default(),
+            transact_verifier_program: Pubkey::default(),
+            merge_verifier_program: Pubkey::default(),
+            guardian: Pubkey::default(),
+            paused: false,
+            paused_at: 0,
+            bump: 0,
+            tree_bump: 0,
+            vault_bump: 0,
+            current_generation: 0,
+            nullifier_shards: 0,
+            sol_limits: RateLimits::unlimited(),
+            config_sequence: 0,
+            unpaused_at: 0,
+            pending_verifier_program: Pubkey::default(),
+            verifier_proposed_at: 0,
+            _reserved: [0; ACCOUNT_RESERVED_BYTES - 56],
+            sol_denominations: vec![],
+        }
+    }
+
+    fn mint(fee_bps: u16, min_fee: u64) -> MintConfig {
+        MintConfig {
+            mint: Pubkey::default(),
+            enabled: true,
+            limits: RateLimits::unlimited(),
+            decimals: 6,
+            fee_bps,
+            min_fee,
+            bump: 0,
+            denominations: vec![],
+        }
+    }
+
+    fn capped(max_withdrawal: u64, epoch_withdrawal_cap: u64) -> RateLimits {
+        RateLimits {
+            max_withdrawal,
+            epoch_withdrawal_cap,
+            ..RateLimits::unlimited()
+        }
+    }
+
+    #[test]
+    fn withdrawals_accumulate_within_epoch() {
+        let mut limits = capped(u64::MAX, 100);
+        limits.record_withdrawal(60, 7).unwrap();
+        limits.record_withdrawal(40, 7).unwrap();
+        assert_eq!((limits.epoch, limits.epoch_withdrawn), (7, 100));
+        assert!(limits.record_withdrawal(1, 7).is_err());
+    }
+
+    #[test]
+    fn epoch_rollover_resets_total() {
+        let mut limits = capped(u64::MAX, 100);
+        limits.record_withdrawal(90, 7).unwrap();
+        assert!(limits.record_withdrawal(20, 7).is_err());
+
+        limits.record_withdrawal(20, 8).unwrap();
+        assert_eq!((limits.epoch, limits.epoch_withdrawn), (8, 20));
+    }
+
+    #[test]
+    fn per_withdrawal_cap_applies_in_any_epoch() {
+        let mut limits = capped(50, u64::MAX);
+        assert!(limits.record_withdrawal(51, 7).is_err());
+        assert!(limits.record_withdrawal(51, 8).is_err());
+        limits.record_withdrawal(50, 8).unwrap();
+        assert_eq!(limits.epoch_withdrawn, 50);
+    }
+
+    #[test]
+    fn unlimited_accepts_everything() {
+        let mut limits = RateLimits::unlimited();
+        limits.check_deposit(0).unwrap();
+        limits.check_deposit(u64::MAX).unwrap();
+        limits.record_withdrawal(u64::MAX, 1).unwrap();
+        // The epoch total saturates the cap, so another withdrawal overflows
+        assert!(limits.record_withdrawal(1, 1).is_err());
+        limits.record_withdrawal(1, 2).unwrap();
+    }
+
+    #[test]
+    fn fee_cap_at_max_bps() {
+        let config = pool(MAX_FEE_BPS);
+        assert_eq!(config.max_fee(10_000).unwrap(), 1_000);
+        // No overflow at the largest amount
+        assert_eq!(config.max_fee(u64::MAX).unwrap(), u64::MAX / 10);
+    }
+
+    /// Split `gross` the way the client does: fee on the gross, recipient gets the rest
+    fn client_split(gross: u64, fee_bps: u16) -> (u64, u64) {
+        let fee = gross * fee_bps as u64 / BPS_DENOMINATOR;
+        (gross - fee, fee)
+    }
+
+    #[test]
+    fn fee_cap_applies_to_gross_amount() {
+        let config = pool(25);
+        for gross in [1, 399, 400, 1_000_000, 123_456_789, 10_000_000_000] {
+            let (amount, fee) = client_split(gross, 25);
+            config.check_fee(amount, fee).unwrap();
+        }
+        // 2_500 on 997_500 net is exactly 25 bps of the 1_000_000 gross
+        config.check_fee(997_500, 2_500).unwrap();
+        assert!(config.check_fee(997_499, 2_501).is_err());
+        assert!(config.check_fee(1_000_000, 2_507).is_err());
+        assert!(config.check_fee(u64::MAX, 1).is_err());
+    }
+
+    #[test]
+    fn mint_fee_cap_applies_to_gross_amount() {
+        let config = mint(25, 100);
+        for gross in [100, 10_000, 1_000_000, 10_000_000_000] {
+            let (amount, fee) = client_split(gross, 25);
+            config.check_fee(amount, fee).unwrap();
+        }
+        // The flat fee is allowed once the gross is large enough to carry it
+        config.check_fee(900, 100).unwrap();
+        assert!(config.check_fee(899, 101).is_err());
+        config.check_fee(997_500, 2_500).unwrap();
+        assert!(config.check_fee(997_499, 2_501).is_err());
+    }
+
+    #[test]
+    fn fee_cap_rounds_down_at_small_amounts() {
+        let config = pool(25);
+        assert_eq!(config.max_fee(0).unwrap(), 0);
+        assert_eq!(config.max_fee(399).unwrap(), 0);
+        assert_eq!(config.max_fee(400).unwrap(), 1);
+        assert_eq!(config.max_fee(799).unwrap(), 1);
+        assert_eq!(pool(0).max_fee(u64::MAX).unwrap(), 0);
+    }
+
+    #[test]
+    fn mint_min_fee_raises_cap() {
+        let config = mint(25, 100);
+        // 25 bps of 10_000 is 25; the flat fee lifts the cap to 100
+        assert_eq!(config.max_fee(10_000).unwrap(), 100);
+        // Above the flat fee the bps cap applies
+        assert_eq!(config.max_fee(1_000_000).unwrap(), 2_500);
+    }
+
+    #[test]
+    fn mint_min_fee_never_exceeds_max_bps() {
+        let config = mint(25, 1_000_000);
+        assert_eq!(config.max_fee(10_000).unwrap(), 1_000);
+        assert_eq!(config.max_fee(5).unwrap(), 0);
+        assert_eq!(config.max_fee(0).unwrap(), 0);
+        assert!(config.max_fee(10_000).unwrap() < 10_000);
+    }
+
+    #[test]
+    fn mint_fee_config_bounds() {
+        MintConfig::check_fee_config(10_000, MAX_FEE_BPS, 1_000).unwrap();
+        assert!(MintConfig::check_fee_config(10_000, MAX_FEE_BPS + 1, 0).is_err());
+        assert!(MintConfig::check_fee_config(10_000, 25, 1_001).is_err());
+        assert!(MintConfig::check_fee_config(1, 25, 1).is_err());
+    }
+
+    #[test]
+    fn denominated_deposits_accept_listed_amounts_only() {
+        let mut config = pool(0);
+        config.sol_denominations = vec![100_000_000, 1_000_000_000];
+        assert!(config.is_allowed_deposit(100_000_000));
+        assert!(config.is_allowed_deposit(1_000_000_000));
+        assert!(!config.is_allowed_deposit(100_000_001));
+        assert!(!config.is_allowed_deposit(0));
+        assert_eq!(config.deposit_event_amount(100_000_000), None);
+
+        let mut usdc = mint(0, 0);
+        usdc.denominations = vec![10_000_000];
+        assert!(usdc.is_allowed_deposit(10_000_000));
+        assert!(!usdc.is_allowed_deposit(5_000_000));
+        assert_eq!(usdc.deposit_event_amount(10_000_000), None);
+    }
+
+    #[test]
+    fn undenominated_deposits_accept_any_amount() {
+        let config = pool(0);
+        assert!(config.is_allowed_deposit(1));
+        assert!(config.is_allowed_deposit(123_456_789));
+        assert_eq!(config.deposit_event_amount(42), Some(42));
+        assert!(mint(0, 0).is_allowed_deposit(7));
+    }
+
+    #[test]
+    fn denomination_list_validation() {
+        check_denominations(&[]).unwrap();
+        check_denominations(&[1, 10, 100]).unwrap();
+        check_denominations(&[1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+        assert!(check_denominations(&[1, 2, 3, 4, 5, 6, 7, 8, 9]).is_err());
+        assert!(check_denominations(&[0, 10]).is_err());
+        assert!(check_denominations(&[10, 10]).is_err());
+        assert!(check_denominations(&[100, 10]).is_err());
+    }
+
+    const DAY: i64 = 24 * 60 * 60;
+
+    #[test]
+    fn withdrawals_open_while_live_and_after_escape_delay() {
+        let mut config = pool(0);
+        assert!(config.withdrawals_open(1_000));
+
+        config.set_paused(true, 1_000);
+        assert!(!config.withdrawals_open(1_000));
+        assert!(!config.withdrawals_open(1_000 + ESCAPE_HATCH_DELAY_SECONDS - 1));
+        assert!(config.withdrawals_open(1_000 + ESCAPE_HATCH_DELAY_SECONDS));
+
+        config.set_paused(false, 1_000 + DAY);
+        assert!(config.withdrawals_open(1_000 + DAY));
+    }
+
+    #[test]
+    fn repausing_a_paused_pool_keeps_its_start() {
+        let mut config = pool(0);
+        config.set_paused(true, 1_000);
+        config.set_paused(true, 1_000 + 10 * DAY);
+        assert_eq!(config.paused_at, 1_000);
+        assert!(config.withdrawals_open(1_000 + ESCAPE_HATCH_DELAY_SECONDS));
+    }
+
+    #[test]
+    fn unpause_and_repause_cannot_reset_the_escape_clock() {
+        let start = 100 * DAY;
+        let mut config = pool(0);
+        config.set_paused(true, start);
+        config.set_paused(false, start + 13 * DAY);
+        config.set_paused(true, start + 13 * DAY + 1);
+        assert_eq!(config.paused_at, start);
+        assert!(config.withdrawals_open(start + ESCAPE_HATCH_DELAY_SECONDS));
+    }
+
+    #[test]
+    fn pause_after_long_live_period_starts_a_new_clock() {
+        let start = 100 * DAY;
+        let mut config = pool(0);
+        config.set_paused(true, start);
+        config.set_paused(false, start + DAY);
+        let repause = start + DAY + ESCAPE_HATCH_DELAY_SECONDS;
+        config.set_paused(true, repause);
+        assert_eq!(config.paused_at, repause);
+        assert!(!config.withdrawals_open(repause + DAY));
+    }
+
+    #[test]
+    fn first_pause_starts_the_clock() {
+        let mut config = pool(0);
+        config.set_paused(true, 1_000);
+        assert_eq!(config.paused_at, 1_000);
+    }
+
+    #[test]
+    fn zeroed_limits_cannot_block_the_escape_hatch() {
+        // The admin unpauses, sets every cap to 0 (`set_limits`) and re-pauses
+        let start = 100 * DAY;
+        let mut config = pool(0);
+        config.set_paused(true, start);
+        config.set_paused(false, start + DAY);
+        config.sol_limits.update(0, 0, 0, 0).unwrap();
+        config.set_paused(true, start + DAY);
+
+        // Limits still bind until the hatch opens...
+        let before = start + ESCAPE_HATCH_DELAY_SECONDS - 1;
+        assert!(!config.withdrawals_open(before));
+        assert!(!config.escape_hatch_open(before));
+        assert!(config.sol_limits.clone().record_withdrawal(1, 0).is_err());
+
+        // ...on the original schedule, after which the handlers skip them
+        let hatch = start + ESCAPE_HATCH_DELAY_SECONDS;
+        assert!(config.withdrawals_open(hatch));
+        assert!(config.escape_hatch_open(hatch));
+    }
+
+    #[test]
+    fn zeroed_limits_before_first_pause_cannot_block_the_escape_hatch() {
+        let mut config = pool(0);
+        config.sol_limits.update(0, 0, 0, 0).unwrap();
+        config.set_paused(true, 1_000);
+        assert!(config.escape_hatch_open(1_000 + ESCAPE_HATCH_DELAY_SECONDS));
+    }
+
+    #[test]
+    fn live_pool_never_has_an_open_hatch() {
+        let mut config = pool(0);
+        assert!(!config.escape_hatch_open(i64::MAX));
+        config.set_paused(true, 1_000);
+        config.set_paused(false, 1_000 + ESCAPE_HATCH_DELAY_SECONDS);
+        assert!(!config.escape_hatch_open(1_000 + ESCAPE_HATCH_DELAY_SECONDS));
+    }
+
+    #[test]
+    fn verifier_change_waits_for_time_lock() {
+        let mut config = pool(0);
+        let old = config.verifier_program;
+        let new = Pubkey::new_unique();
+        config.propose_verifier(new, 1_000).unwrap();
+        assert!(config.apply_verifier(1_000).is_err());
+        assert!(config.apply_verifier(1_000 + ESCAPE_HATCH_DELAY_SECONDS - 1).is_err());
+        assert_eq!(config.verifier_program, old);
+
+        assert_eq!(config.apply_verifier(1_000 + ESCAPE_HATCH_DELAY_SECONDS).unwrap(), old);
+        assert_eq!(config.verifier_program, new);
+        assert_eq!(config.pending_verifier_program, Pubkey::default());
+        // Nothing left to apply
+        assert!(config.apply_verifier(i64::MAX).is_err());
+    }
+
+    #[test]
+    fn pause_cancels_pending_verifier_change() {
+        // The admin unpauses, proposes a reject-all verifier and re-pauses
+        let start = 100 * DAY;
+        let mut config = pool(0);
+        let old = config.verifier_program;
+        config.set_paused(true, start);
+        config.set_paused(false, start + DAY);
+        config.propose_verifier(Pubkey::new_unique(), start + DAY).unwrap();
+        config.set_paused(true, start + DAY);
+
+        // Frozen while paused, and the proposal is gone once unpaused
+        let later = start + DAY + ESCAPE_HATCH_DELAY_SECONDS;
+        assert!(config.apply_verifier(later).is_err());
+        assert!(config.propose_verifier(Pubkey::new_unique(), later).is_err());
+        config.set_paused(false, later);
+        assert!(config.apply_verifier(later).is_err());
+        assert_eq!(config.verifier_program, old);
+    }
+
+    #[test]
+    fn default_verifier_cannot_be_proposed() {
+        let mut config = pool(0);
+        assert!(config.propose_verifier(Pubkey::default(), 1_000).is_err());
+    }
+
+    #[test]
+    fn empty_root_matches_circuit() {
+        // Same constant as test_empty_tree_root in circuits-noir/common/src/merkle.nr
+        let tree = new_tree(4);
+        let expected = [
+            0x21, 0x34, 0xe7, 0x6a, 0xc5, 0xd2, 0x1a, 0xab, 0x18, 0x6c, 0x2b, 0xe1, 0xdd, 0x8f,
+            0x84, 0xee, 0x88, 0x0a, 0x1e, 0x46, 0xea, 0xf7, 0x12, 0xf9, 0xd3, 0x71, 0xb6, 0xdf,
+            0x22, 0x19, 0x1f, 0x3e,
+        ];
+        assert_eq!(tree.current_root, expected);
+    }
+
+    #[test]
+    fn root_history_wraps_around() {
+        let mut tree = new_tree(4);
+        let mut roots = vec![tree.current_root];
+        for i in 0..6 {
+            assert_eq!(tree.insert(&leaf(i)).unwrap(), i as u64);
+            roots.push(tree.current_root);
+        }
+
+        // Six inserts into a 4-slot ring: roots 0 and 1 were overwritten
+        assert_eq!(tree.root_history_index, 6);
+        assert_eq!(tree.root_history_len(), 4);
+        assert!(!tree.is_known_root(&roots[0]));
+        assert!(!tree.is_known_root(&roots[1]));
+        for root in &roots[2..] {
+            assert!(tree.is_known_root(root));
+        }
+    }
+
+    #[test]
+    fn root_history_len_before_wrap() {
+        let mut tree = new_tree(4);
+        let empty_root = tree.current_root;
+        tree.insert(&leaf(0)).unwrap();
+        assert_eq!(tree.root_history_len(), 1);
+        assert!(tree.is_known_root(&empty_root));
+        // Unwritten slots hold zeros, which must never match
+        assert!(!tree.is_known_root(&[0u8; 32]));
+    }
+
+    #[test]
+    fn insert_fails_when_full() {
+        let mut tree = Box::new(MerkleTree::zeroed());
+        tree.height = 2;
+        tree.root_history_size = 4;
+        tree.init_zeros().unwrap();
+        for i in 0..4 {
+            tree.insert(&leaf(i)).unwrap();
+        }
+        assert!(tree.is_full());
+        assert!(tree.insert(&leaf(4)).is_err());
+        assert_eq!(tree.next_index, 4);
+    }
+
+    #[test]
+    fn version_bytes_have_fixed_offsets() {
+        // An upgrade path must read the version before it knows the layout
+        let mut data = Vec::new();
+        pool(0).try_serialize(&mut data).unwrap();
+        assert_eq!(data[8], POOL_CONFIG_VERSION);
+        assert_eq!(8 + std::mem::offset_of!(MerkleTree, version), 8292);
+    }
+
+    #[test]
+    fn pool_config_reserved_bytes_precede_denominations() {
+        let mut config = pool(0);
+        config.config_sequence = u64::MAX;
+        config.unpaused_at = -1;
+        config.sol_denominations = vec![7];
+        let mut data = Vec::new();
+        config.try_serialize(&mut data).unwrap();
+
+        // config_sequence and unpaused_at open the reserved region at a fixed offset
+        const RESERVED_START: usize = 345;
+        const RESERVED_END: usize = RESERVED_START + ACCOUNT_RESERVED_BYTES;
+        assert_eq!(data[RESERVED_START..RESERVED_START + 16], [0xff; 16]);
+        assert!(data[RESERVED_START + 16..RESERVED_END].iter().all(|b| *b == 0));
+        assert_eq!(data[RESERVED_END..RESERVED_END + 4], 1u32.to_le_bytes());
+        assert_eq!(data[RESERVED_END + 4..], 7u64.to_le_bytes());
+        assert_eq!(
+            RESERVED_END + 4 + 8 * MAX_DENOMINATIONS,
+            8 + PoolConfig::INIT_SPACE
+        );
+    }
+
+    #[test]
+    fn merkle_tree_reserved_bytes_end_the_account() {
+        let sequence = std::mem::offset_of!(MerkleTree, sequence);
+        assert_eq!(sequence, 9600);
+        assert_eq!(std::mem::offset_of!(MerkleTree, _reserved), sequence + 8);
+        assert_eq!(
+            sequence + ACCOUNT_RESERVED_BYTES,
+            std::mem::size_of::<MerkleTree>()
+        );
+    }
+
+    #[test]
+    fn depositor_closes_record_any_time() {
+        let depositor = Pubkey::new_unique();
+        let record = CommitmentRecord {
+            depositor,
+            generation: 3,
+            index: 0,
+            commitment: [0; 32],
+            bump: 0,
+        };
+        assert!(record.can_close(&depositor, 3));
+        assert!(record.can_close(&depositor, 4));
+    }
+
+    #[test]
+    fn third_party_closes_record_only_after_rollover() {
+        let record = CommitmentRecord {
+            depositor: Pubkey::new_unique(),
+            generation: 3,
+            index: 0,
+            commitment: [0; 32],
+            bump: 0,
+        };
+        let other = Pubkey::new_unique();
+        assert!(!record.can_close(&other, 3));
+        assert!(record.can_close(&other, 4));
+    }
+}
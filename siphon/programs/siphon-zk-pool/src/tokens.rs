@@ -0,0 +1,83 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::spl_token_2022::extension::{
+    BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+};
+use anchor_spl::token_interface::spl_token_2022::state::Mint as MintState;
+use anchor_spl::token_interface::{self, spl_token_2022, Mint, TokenAccount, TransferChecked};
+use anchor_spl::token::spl_token;
+use crate::errors::ZkPoolError;
+
+/// Token-2022 mint extensions the pool refuses to hold:
+/// - `PermanentDelegate`: a third party can move the pool's tokens at will
+/// - `TransferHook`: withdrawals would depend on an arbitrary external program
+/// - `NonTransferable`: notes could never be withdrawn
+/// - `MintCloseAuthority`: the mint could be closed and recreated with other extensions
+const BLOCKED_MINT_EXTENSIONS: [ExtensionType; 4] = [
+    ExtensionType::PermanentDelegate,
+    ExtensionType::TransferHook,
+    ExtensionType::NonTransferable,
+    ExtensionType::MintCloseAuthority,
+];
+
+/// Reject mints carrying extensions that put pool funds at risk. Classic SPL Token
+/// mints have no extensions and always pass.
+pub fn check_mint_extensions(mint: &InterfaceAccount<Mint>) -> Result<()> {
+    let info = mint.to_account_info();
+    let data = info.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data)?;
+    for extension in state.get_extension_types()? {
+        require!(
+            !BLOCKED_MINT_EXTENSIONS.contains(&extension),
+            ZkPoolError::UnsupportedMintExtension
+        );
+    }
+    Ok(())
+}
+
+/// `transfer_checked` into a pool token account, returning the amount that actually
+/// arrived. This is less than `amount` for mints with a Token-2022 transfer fee.
+pub fn transfer_in<'info>(
+    token_program: &AccountInfo<'info>,
+    from: &InterfaceAccount<'info, TokenAccount>,
+    to: &mut InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    authority: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<u64> {
+    let before = to.amount;
+    token_interface::transfer_checked(
+        CpiContext::new(
+            token_program.clone(),
+            TransferChecked {
+                from: from.to_account_info(),
+                mint: mint.to_account_info(),
+                to: to.to_account_info(),
+                authority: authority.clone(),
+            },
+        ),
+        amount,
+        mint.decimals,
+    )?;
+    to.reload()?;
+
+    let received = to.amount.checked_sub(before).ok_or(ZkPoolError::Overflow)?;
+    require!(received > 0, ZkPoolError::InvalidAmount);
+    Ok(received)
+}
+
+/// Whether `info` is an SPL Token or Token-2022 account owned by `authority`
+pub fn is_token_account_of(info: &AccountInfo, authority: &Pubkey) -> bool {
+    if *info.owner != spl_token::ID && *info.owner != spl_token_2022::ID {
+        return false;
+    }
+    let Ok(data) = info.try_borrow_data() else {
+        return false;
+    };
+    TokenAccount::try_deserialize(&mut &data[..]).is_ok_and(|account| account.owner == *authority)
+}
+
+/// Whether `authority` still fully controls `account`: it is the owner and no
+/// delegate or close authority has been set
+pub fn has_sole_authority(account: &TokenAccount, authority: &Pubkey) -> bool {
+    account.owner == *authority && account.delegate.is_none() && account.close_authority.is_none()
+}
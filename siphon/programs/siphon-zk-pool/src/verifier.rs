@@ -0,0 +1,62 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::invoke;
+use crate::errors::ZkPoolError;
+
+/// Size of a serialized BN254 field element
+pub const FIELD_SIZE: usize = 32;
+
+/// Encode a u64 as a big-endian BN254 field element
+pub fn u64_to_field(value: u64) -> [u8; 32] {
+    let mut field = [0u8; 32];
+    field[24..].copy_from_slice(&value.to_be_bytes());
+    field
+}
+
+/// Asset id of a mint as a BN254 field element: sha256(mint) with the top byte cleared.
+/// SOL uses `Pubkey::default()`; wSOL deposits and withdrawals share that asset id.
+pub fn asset_id(mint: &Pubkey) -> [u8; 32] {
+    let mut field = hash(mint.as_ref()).to_bytes();
+    field[0] = 0;
+    field
+}
+
+/// Serialize public inputs in gnark's public witness format:
+/// nb_public (u32 BE) | nb_secret (u32 BE) | length (u32 BE) | elements (32 bytes BE each)
+fn encode_public_witness(public_inputs: &[[u8; 32]]) -> Vec<u8> {
+    let count = public_inputs.len() as u32;
+    let mut witness = Vec::with_capacity(12 + public_inputs.len() * FIELD_SIZE);
+    witness.extend_from_slice(&count.to_be_bytes());
+    witness.extend_from_slice(&0u32.to_be_bytes());
+    witness.extend_from_slice(&count.to_be_bytes());
+    for input in public_inputs {
+        witness.extend_from_slice(input);
+    }
+    witness
+}
+
+/// Verify a Groth16 proof by CPI into the Sunspot verifier program generated
+/// for the Noir circuit. Instruction data is `proof || public_witness`; the
+/// verifier fails the transaction if the proof does not check out.
+pub fn verify_proof(
+    verifier_program: &AccountInfo,
+    proof: &[u8],
+    public_inputs: &[[u8; 32]],
+) -> Result<()> {
+    require!(!proof.is_empty(), ZkPoolError::InvalidProof);
+
+    let mut data = Vec::with_capacity(proof.len() + 12 + public_inputs.len() * FIELD_SIZE);
+    data.extend_from_slice(proof);
+    data.extend_from_slice(&encode_public_witness(public_inputs));
+
+    let ix = Instruction {
+        program_id: verifier_program.key(),
+        accounts: vec![],
+        data,
+    };
+
+    invoke(&ix, &[verifier_program.clone()]).map_err(|_| ZkPoolError::InvalidProof)?;
+
+    Ok(())
+}
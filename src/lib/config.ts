@@ -9,3 +9,4 @@ export const NEXT_PUBLIC_RANGE_API_URL = 'https://api.range.org/v1';
 export const NEXT_PUBLIC_ALT_ADDRESS = 'AymEFi9z9RZ4ZZNsLpg83PqXk9jn3KtsyAG8P1RSRXuw';
 export const NEXT_PUBLIC_PROGRAM_ID = '3CVsp1zayXhNsT8Ktrh85rTewvBJxWy8VcUtQAKdnQMb';
 export const NEXT_PUBLIC_ZK_POOL_PROGRAM_ID = '3CVsp1zayXhNsT8Ktrh85rTewvBJxWy8VcUtQAKdnQMb';
+export const NEXT_PUBLIC_ZK_POOL_ID = '0';
@@ -67,6 +67,10 @@ import {
   getPoolConfigPDA,
   getPoolVaultPDA,
   getCommitmentRecordPDA,
+  getMintConfigPDA,
+  getAssetId,
+  fetchCurrentGeneration,
+  MERKLE_TREE_NEXT_INDEX_OFFSET,
 } from './relayer-core';
 import {
   getAssociatedTokenAddress,
@@ -90,7 +94,7 @@ async function ensurePoseidon() {
 }
 
 // Generate commitment client-side (same logic as relayer-core but runs in browser)
-async function generateCommitment(value: bigint): Promise<{
+async function generateCommitment(value: bigint, mint?: PublicKey): Promise<{
   commitment: string;
   nullifier: string;
   secret: string;
@@ -108,8 +112,9 @@ async function generateCommitment(value: bigint): Promise<{
   // precommitment = Poseidon(nullifier, secret)
   const precommitment = wasm.poseidonHashString([nullifier, secret]);
 
-  // commitment = Poseidon(value, precommitment)
-  const commitment = wasm.poseidonHashString([value.toString(), precommitment]);
+  // commitment = Poseidon(value, asset_id, precommitment), as derived on-chain
+  const assetId = await getAssetId(mint);
+  const commitment = wasm.poseidonHashString([value.toString(), assetId, precommitment]);
 
   return { commitment, nullifier, secret, precommitment };
 }
@@ -158,15 +163,15 @@ export async function depositSOLClientSide(
     console.log(`[Client Deposit] Depositing ${lamports / LAMPORTS_PER_SOL} SOL`);
 
     // Generate commitment/nullifier client-side
-    const { commitment, nullifier, secret } = await generateCommitment(BigInt(lamports));
+    const { commitment, nullifier, secret, precommitment } = await generateCommitment(BigInt(lamports));
 
-    // Convert commitment to bytes
-    const commitmentBN = new BN(commitment);
-    const commitmentBytes = commitmentBN.toArray('be', 32);
+    // The program derives the commitment from the precommitment, amount and asset
+    const precommitmentBytes = new BN(precommitment).toArray('be', 32);
 
     // Get PDAs
     const [poolConfig] = getPoolConfigPDA();
-    const [merkleTree] = getMerkleTreePDA();
+    const generation = await fetchCurrentGeneration(connection);
+    const [merkleTree] = getMerkleTreePDA(generation);
     const [poolVault] = getPoolVaultPDA();
 
     // Fetch next leaf index from on-chain state
@@ -175,9 +180,12 @@ export async function depositSOLClientSide(
       return { success: false, error: 'Merkle tree not initialized' };
     }
 
-    // Parse next_index from account data (offset 40, u64 LE)
-    const nextIndex = new BN(merkleTreeAccount.data.slice(40, 48), 'le').toNumber();
-    const [commitmentRecord] = getCommitmentRecordPDA(nextIndex);
+    // Parse next_index from account data (u64 LE)
+    const nextIndex = new BN(
+      merkleTreeAccount.data.slice(MERKLE_TREE_NEXT_INDEX_OFFSET, MERKLE_TREE_NEXT_INDEX_OFFSET + 8),
+      'le'
+    ).toNumber();
+    const [commitmentRecord] = getCommitmentRecordPDA(nextIndex, generation);
 
     // Encrypt output
     const encryptedOutputBytes = encryptOutput({
@@ -189,12 +197,12 @@ export async function depositSOLClientSide(
     const encryptedOutput = Buffer.from(encryptedOutputBytes).toString('hex');
 
     // Build deposit_sol instruction
-    // Instruction layout (Borsh): discriminator + commitment + vec<encrypted_output> + amount + leaf_index
+    // Instruction layout (Borsh): discriminator + precommitment + vec<encrypted_output> + amount + leaf_index
     const discriminator = Buffer.from([
       0x6c, 0x51, 0x4e, 0x75, 0x7d, 0x9b, 0x38, 0xc8, // sha256("global:deposit_sol")[0..8]
     ]);
 
-    // Allocate buffer: 8 (disc) + 32 (commitment) + 4 (vec len) + encrypted_output + 8 (amount) + 8 (leaf_index)
+    // Allocate buffer: 8 (disc) + 32 (precommitment) + 4 (vec len) + encrypted_output + 8 (amount) + 8 (leaf_index)
     const instructionData = Buffer.alloc(8 + 32 + 4 + encryptedOutputBytes.length + 8 + 8);
     let offset = 0;
 
@@ -202,8 +210,8 @@ export async function depositSOLClientSide(
     discriminator.copy(instructionData, offset);
     offset += 8;
 
-    // Commitment [u8; 32]
-    Buffer.from(commitmentBytes).copy(instructionData, offset);
+    // Precommitment [u8; 32]
+    Buffer.from(precommitmentBytes).copy(instructionData, offset);
     offset += 32;
 
     // Encrypted output Vec<u8> (length + data)
@@ -268,7 +276,7 @@ export async function depositSOLClientSide(
         console.log('[Client Deposit] Transaction may have already succeeded, checking on-chain state...');
 
         // Check if commitment was recorded on-chain
-        const [checkCommitmentRecord] = getCommitmentRecordPDA(nextIndex);
+        const [checkCommitmentRecord] = getCommitmentRecordPDA(nextIndex, generation);
         const recordInfo = await connection.getAccountInfo(checkCommitmentRecord);
 
         if (recordInfo) {
@@ -331,15 +339,16 @@ export async function depositSPLClientSide(
     console.log(`[Client Deposit SPL] Depositing ${amount / Math.pow(10, decimals)} tokens (${mintAddress.toBase58()})`);
 
     // Generate commitment/nullifier client-side
-    const { commitment, nullifier, secret } = await generateCommitment(BigInt(amount));
+    const { commitment, nullifier, secret, precommitment } = await generateCommitment(BigInt(amount), mintAddress);
 
-    // Convert commitment to bytes
-    const commitmentBN = new BN(commitment);
-    const commitmentBytes = commitmentBN.toArray('be', 32);
+    // The program derives the commitment from the precommitment, amount and asset
+    const precommitmentBytes = new BN(precommitment).toArray('be', 32);
+    const [mintConfig] = getMintConfigPDA(mintAddress);
 
     // Get PDAs
     const [poolConfig] = getPoolConfigPDA();
-    const [merkleTree] = getMerkleTreePDA();
+    const generation = await fetchCurrentGeneration(connection);
+    const [merkleTree] = getMerkleTreePDA(generation);
     const [poolVault] = getPoolVaultPDA();
 
     // Pool token account is an ATA owned by poolVault
@@ -372,9 +381,12 @@ export async function depositSPLClientSide(
       return { success: false, error: 'Merkle tree not initialized' };
     }
 
-    // Parse next_index from account data (offset 40, u64 LE)
-    const nextIndex = new BN(merkleTreeAccount.data.slice(40, 48), 'le').toNumber();
-    const [commitmentRecord] = getCommitmentRecordPDA(nextIndex);
+    // Parse next_index from account data (u64 LE)
+    const nextIndex = new BN(
+      merkleTreeAccount.data.slice(MERKLE_TREE_NEXT_INDEX_OFFSET, MERKLE_TREE_NEXT_INDEX_OFFSET + 8),
+      'le'
+    ).toNumber();
+    const [commitmentRecord] = getCommitmentRecordPDA(nextIndex, generation);
 
     // Encrypt output
     const encryptedOutputBytes = encryptOutput({
@@ -386,12 +398,12 @@ export async function depositSPLClientSide(
     const encryptedOutput = Buffer.from(encryptedOutputBytes).toString('hex');
 
     // Build deposit_spl instruction
-    // Instruction layout (Borsh): discriminator + commitment + vec<encrypted_output> + amount + leaf_index
+    // Instruction layout (Borsh): discriminator + precommitment + vec<encrypted_output> + amount + leaf_index
     const discriminator = Buffer.from([
       0xe0, 0x00, 0xc6, 0xaf, 0xc6, 0x2f, 0x69, 0xcc, // sha256("global:deposit_spl")[0..8]
     ]);
 
-    // Allocate buffer: 8 (disc) + 32 (commitment) + 4 (vec len) + encrypted_output + 8 (amount) + 8 (leaf_index)
+    // Allocate buffer: 8 (disc) + 32 (precommitment) + 4 (vec len) + encrypted_output + 8 (amount) + 8 (leaf_index)
     const instructionData = Buffer.alloc(8 + 32 + 4 + encryptedOutputBytes.length + 8 + 8);
     let offset = 0;
 
@@ -399,8 +411,8 @@ export async function depositSPLClientSide(
     discriminator.copy(instructionData, offset);
     offset += 8;
 
-    // Commitment [u8; 32]
-    Buffer.from(commitmentBytes).copy(instructionData, offset);
+    // Precommitment [u8; 32]
+    Buffer.from(precommitmentBytes).copy(instructionData, offset);
     offset += 32;
 
     // Encrypted output Vec<u8> (length + data)
@@ -422,6 +434,7 @@ export async function depositSPLClientSide(
         { pubkey: poolConfig, isSigner: false, isWritable: false }, // poolConfig
         { pubkey: merkleTree, isSigner: false, isWritable: true }, // merkleTree
         { pubkey: mintAddress, isSigner: false, isWritable: false }, // tokenMint
+        { pubkey: mintConfig, isSigner: false, isWritable: false }, // mintConfig
         { pubkey: depositorTokenAccount, isSigner: false, isWritable: true }, // depositorTokenAccount
         { pubkey: poolTokenAccount, isSigner: false, isWritable: true }, // poolTokenAccount
         { pubkey: poolVault, isSigner: false, isWritable: false }, // poolVault
@@ -482,7 +495,7 @@ export async function depositSPLClientSide(
         console.log('[Client Deposit SPL] Transaction may have already succeeded, checking on-chain state...');
 
         // Check if commitment was recorded on-chain
-        const [checkCommitmentRecord] = getCommitmentRecordPDA(nextIndex);
+        const [checkCommitmentRecord] = getCommitmentRecordPDA(nextIndex, generation);
         const recordInfo = await connection.getAccountInfo(checkCommitmentRecord);
 
         if (recordInfo) {
@@ -167,13 +167,17 @@ export class NoirZkClient {
         };
       }
 
+      if (!params.prover) {
+        return { success: false, error: 'A withdrawal prover is required' };
+      }
+
       const zkClient = await getZkPoolClient();
       const result = await zkClient.withdrawSOL(params.recipientAddress, params.lamports, {
         commitment: utxo.commitment,
         nullifier: utxo.nullifier,
         secret: utxo.secret,
         value: utxo.value,
-      });
+      }, params.prover);
 
       if (result.success) {
         // Mark UTXO as spent
@@ -272,6 +276,10 @@ export class NoirZkClient {
         };
       }
 
+      if (!params.prover) {
+        return { success: false, error: 'A withdrawal prover is required' };
+      }
+
       const zkClient = await getZkPoolClient();
 
       // Currently only USDC is supported
@@ -282,7 +290,7 @@ export class NoirZkClient {
           nullifier: utxo.nullifier,
           secret: utxo.secret,
           value: utxo.value,
-        });
+        }, params.prover);
 
         if (result.success) {
           markUtxoSpent(utxo.commitment);
@@ -7,7 +7,7 @@
 import { Connection, PublicKey, Keypair } from '@solana/web3.js';
 import { BN } from '@coral-xyz/anchor';
 import bs58 from 'bs58';
-import { NEXT_PUBLIC_ZK_POOL_PROGRAM_ID, NEXT_PUBLIC_SOLANA_RPC_URL } from '../config';
+import { NEXT_PUBLIC_ZK_POOL_PROGRAM_ID, NEXT_PUBLIC_ZK_POOL_ID, NEXT_PUBLIC_SOLANA_RPC_URL } from '../config';
 
 // Program ID for siphon-zk-pool (deployed to devnet)
 export const ZK_POOL_PROGRAM_ID = new PublicKey(
@@ -16,55 +16,183 @@ export const ZK_POOL_PROGRAM_ID = new PublicKey(
 
 // Merkle tree configuration
 export const MERKLE_TREE_HEIGHT = 20;
-export const ROOT_HISTORY_SIZE = 32;
+// Capacity of the on-chain root ring; each pool uses the first root_history_size slots
+// sha256("event:CommitmentInserted")[0..8]
+const COMMITMENT_INSERTED_DISCRIMINATOR = Buffer.from([234, 113, 58, 109, 42, 244, 19, 208]);
+
+export const ROOT_HISTORY_SIZE = 256;
+
+// MerkleTree layout (zero_copy, #[repr(C)]): 8 (disc) + 32 (authority) + 8 (generation), then next_index (u64 LE)
+export const MERKLE_TREE_NEXT_INDEX_OFFSET = 8 + 32 + 8;
+
+// Pool this deployment talks to; every PDA is keyed by the pool's PoolConfig
+export const ZK_POOL_ID = BigInt(NEXT_PUBLIC_ZK_POOL_ID || '0');
+
+// PoolConfig layout (Borsh): 8 (disc) + 1 (version) + 8 (pool_id) + 32 (admin)
+// + 32 (pending_admin) + 32 (relayer), then fee_bps (u16 LE)
+export const POOL_CONFIG_FEE_BPS_OFFSET = 8 + 1 + 8 + 32 + 32 + 32;
+// ... + 2 (fee_bps), then fee_recipient
+export const POOL_CONFIG_FEE_RECIPIENT_OFFSET = POOL_CONFIG_FEE_BPS_OFFSET + 2;
+// ... + 32 (fee_recipient), then verifier_program (withdrawal circuit verifier)
+export const POOL_CONFIG_VERIFIER_PROGRAM_OFFSET = POOL_CONFIG_FEE_RECIPIENT_OFFSET + 32;
+// ... + 32 (verifier) + 32 (transact_verifier) + 32 (merge_verifier) + 32 (guardian)
+// + 1 (paused) + 8 (paused_at) + 3 (bumps), then current_generation (u64 LE)
+export const POOL_CONFIG_GENERATION_OFFSET = POOL_CONFIG_FEE_RECIPIENT_OFFSET + 32 + 32 + 32 + 32 + 32 + 1 + 8 + 3;
+
+// MintConfig layout (Borsh): 8 (disc) + 32 (mint) + 1 (enabled) + 48 (limits)
+// + 1 (decimals), then fee_bps (u16 LE) and min_fee (u64 LE)
+export const MINT_CONFIG_FEE_BPS_OFFSET = 8 + 32 + 1 + 48 + 1;
+export const MINT_CONFIG_MIN_FEE_OFFSET = MINT_CONFIG_FEE_BPS_OFFSET + 2;
+
+// Hard cap on any withdrawal fee (MAX_FEE_BPS on-chain)
+export const MAX_FEE_BPS = 1000;
+
+// WithdrawInputs layout version accepted by withdraw_sol / withdraw_spl
+export const WITHDRAW_INPUTS_VERSION = 3;
+
+function u64LE(value: number | bigint): Buffer {
+  const buf = Buffer.alloc(8);
+  buf.writeBigUInt64LE(BigInt(value));
+  return buf;
+}
 
 // PDA derivations (matching on-chain seeds)
-export function getMerkleTreePDA(): [PublicKey, number] {
+export function getPoolConfigPDA(poolId: bigint = ZK_POOL_ID): [PublicKey, number] {
   return PublicKey.findProgramAddressSync(
-    [Buffer.from('merkle_tree')],
+    [Buffer.from('pool_config'), u64LE(poolId)],
     ZK_POOL_PROGRAM_ID
   );
 }
 
-export function getPoolConfigPDA(): [PublicKey, number] {
+// Tree of the given generation; deposits go to PoolConfig.current_generation
+export function getMerkleTreePDA(generation: number | bigint = 0): [PublicKey, number] {
+  const [poolConfig] = getPoolConfigPDA();
   return PublicKey.findProgramAddressSync(
-    [Buffer.from('pool_config')],
+    [Buffer.from('merkle_tree'), poolConfig.toBuffer(), u64LE(generation)],
     ZK_POOL_PROGRAM_ID
   );
 }
 
 export function getPoolVaultPDA(): [PublicKey, number] {
+  const [poolConfig] = getPoolConfigPDA();
   return PublicKey.findProgramAddressSync(
-    [Buffer.from('pool_vault')],
+    [Buffer.from('pool_vault'), poolConfig.toBuffer()],
     ZK_POOL_PROGRAM_ID
   );
 }
 
-export function getPoolTokenAccountPDA(mint: PublicKey): [PublicKey, number] {
+export function getNullifierPDA(nullifierHash: Uint8Array): [PublicKey, number] {
+  const [poolConfig] = getPoolConfigPDA();
   return PublicKey.findProgramAddressSync(
-    [Buffer.from('pool_token'), mint.toBuffer()],
+    [Buffer.from('nullifier'), poolConfig.toBuffer(), Buffer.from(nullifierHash)],
     ZK_POOL_PROGRAM_ID
   );
 }
 
-export function getNullifierPDA(nullifierHash: Uint8Array): [PublicKey, number] {
+export function getCommitmentRecordPDA(leafIndex: number, generation: number | bigint = 0): [PublicKey, number] {
+  const [poolConfig] = getPoolConfigPDA();
   return PublicKey.findProgramAddressSync(
-    [Buffer.from('nullifier'), Buffer.from(nullifierHash)],
+    [Buffer.from('commitment'), poolConfig.toBuffer(), u64LE(generation), u64LE(leafIndex)],
     ZK_POOL_PROGRAM_ID
   );
 }
 
-export function getCommitmentRecordPDA(leafIndex: number): [PublicKey, number] {
-  const indexBuf = Buffer.alloc(8);
-  indexBuf.writeBigUInt64LE(BigInt(leafIndex));
+// Read the active tree generation from PoolConfig
+export async function fetchCurrentGeneration(connection: Connection): Promise<number> {
+  const [poolConfig] = getPoolConfigPDA();
+  const info = await connection.getAccountInfo(poolConfig);
+  if (!info) {
+    throw new Error('PoolConfig account not found. Program may not be initialized.');
+  }
+  return Number(info.data.readBigUInt64LE(POOL_CONFIG_GENERATION_OFFSET));
+}
+
+export function getMintConfigPDA(mint: PublicKey): [PublicKey, number] {
+  const [poolConfig] = getPoolConfigPDA();
   return PublicKey.findProgramAddressSync(
-    [Buffer.from('commitment'), indexBuf],
+    [Buffer.from('mint_config'), poolConfig.toBuffer(), mint.toBuffer()],
     ZK_POOL_PROGRAM_ID
   );
 }
 
-// Commitment data structure
+// Fee settings a withdrawal is capped by: PoolConfig.fee_bps for SOL, the mint's
+// MintConfig fee_bps/min_fee for SPL tokens
+export interface FeeConfig {
+  feeBps: number;
+  minFee: bigint;
+}
+
+export async function fetchFeeConfig(connection: Connection, mint?: PublicKey): Promise<FeeConfig> {
+  if (!mint) {
+    const [poolConfig] = getPoolConfigPDA();
+    const info = await connection.getAccountInfo(poolConfig);
+    if (!info) {
+      throw new Error('PoolConfig account not found. Program may not be initialized.');
+    }
+    return { feeBps: info.data.readUInt16LE(POOL_CONFIG_FEE_BPS_OFFSET), minFee: 0n };
+  }
+  const [mintConfig] = getMintConfigPDA(mint);
+  const info = await connection.getAccountInfo(mintConfig);
+  if (!info) {
+    throw new Error(`Mint ${mint.toBase58()} is not registered with the pool`);
+  }
+  return {
+    feeBps: info.data.readUInt16LE(MINT_CONFIG_FEE_BPS_OFFSET),
+    minFee: info.data.readBigUInt64LE(MINT_CONFIG_MIN_FEE_OFFSET),
+  };
+}
+
+// Split a gross withdrawal into (amount to recipient, fee) with the largest fee
+// the program accepts. The on-chain cap is taken on the gross amount + fee:
+// fee_bps of it, raised to min_fee but never above MAX_FEE_BPS of it.
+export function splitWithdrawalFee(gross: bigint, config: FeeConfig): { amount: bigint; fee: bigint } {
+  const bps = (value: bigint, feeBps: number) => (value * BigInt(feeBps)) / 10_000n;
+  const ceiling = bps(gross, MAX_FEE_BPS);
+  const floor = config.minFee < ceiling ? config.minFee : ceiling;
+  const byBps = bps(gross, config.feeBps);
+  const fee = byBps > floor ? byBps : floor;
+  return { amount: gross - fee, fee };
+}
+
+// Asset id bound into every note: sha256(mint) with the top byte cleared,
+// as a decimal field element. SOL (and wSOL) notes use the default pubkey.
+export async function getAssetId(mint: PublicKey = PublicKey.default): Promise<string> {
+  const digest = new Uint8Array(await crypto.subtle.digest('SHA-256', mint.toBytes()));
+  digest[0] = 0;
+  return new BN(digest).toString();
+}
+
+// Withdrawal ext_data_hash, as computed on-chain: sha256(recipient || mint || amount
+// || fee || fee_recipient || encrypted_output) with the top byte cleared, as a
+// decimal field element. Amounts are u64 big-endian.
+export async function getExtDataHash(data: {
+  recipient: PublicKey;
+  mint: PublicKey;
+  amount: bigint;
+  fee: bigint;
+  feeRecipient: PublicKey;
+  encryptedOutput: Uint8Array;
+}): Promise<string> {
+  const amount = Buffer.alloc(8);
+  amount.writeBigUInt64BE(data.amount);
+  const fee = Buffer.alloc(8);
+  fee.writeBigUInt64BE(data.fee);
+  const preimage = Buffer.concat([
+    data.recipient.toBuffer(),
+    data.mint.toBuffer(),
+    amount,
+    fee,
+    data.feeRecipient.toBuffer(),
+    Buffer.from(data.encryptedOutput),
+  ]);
+  const digest = new Uint8Array(await crypto.subtle.digest('SHA-256', preimage));
+  digest[0] = 0;
+  return new BN(digest).toString();
+}
+
+// Commitment data structure; a leaf is identified by (generation, index)
 export interface CommitmentEntry {
+  generation: number;
   index: number;
   commitment: Uint8Array;
   commitmentHex: string;
@@ -75,12 +203,14 @@ export interface CommitmentEntry {
 // Tree state read from on-chain MerkleTree account
 export interface OnChainTreeState {
   authority: PublicKey;
+  generation: number;
   nextIndex: number;
   currentRoot: Uint8Array;
-  rootHistory: Uint8Array[]; // 32 entries of 32 bytes each
+  rootHistory: Uint8Array[]; // root_history_size entries of 32 bytes each
   rootHistoryIndex: number;
   height: number;
   bump: number;
+  rootHistorySize: number;
 }
 
 // Local Merkle tree structure (rebuilt from commitments using Poseidon)
@@ -90,6 +220,15 @@ export interface LocalMerkleTree {
   levels: number;
 }
 
+// Indexed leaves of one tree generation
+interface GenerationIndex {
+  commitmentsByIndex: string[];
+  encryptedOutputsByIndex: string[];
+  tree: LocalMerkleTree | null;
+  // Rolled over and fully indexed; older trees receive no more leaves
+  sealed: boolean;
+}
+
 // Singleton state for the embedded relayer
 class RelayerCore {
   private connection: Connection | null = null;
@@ -97,17 +236,14 @@ class RelayerCore {
   // eslint-disable-next-line @typescript-eslint/no-explicit-any
   private poseidonWasm: any = null;
 
-  // Commitment index
+  // Commitment index, across every tree generation
   private utxoIndex = new Map<string, CommitmentEntry>();
   private commitmentIndex = new Map<string, CommitmentEntry>();
-  private encryptedOutputsByIndex: string[] = [];
-  private commitmentsByIndex: string[] = [];
+  private generations = new Map<number, GenerationIndex>();
+  private currentGeneration = 0;
   private isIndexing = false;
   private indexingPromise: Promise<void> | null = null;
 
-  // Local Merkle tree
-  private localTree: LocalMerkleTree | null = null;
-
   // Cache
   private cachedTreeState: OnChainTreeState | null = null;
   private cacheTimestamp = 0;
@@ -181,15 +317,16 @@ class RelayerCore {
     return this.executorKeypair;
   }
 
-  // Read on-chain MerkleTree account state
-  async getTreeState(forceRefresh = false): Promise<OnChainTreeState> {
+  // Read on-chain MerkleTree account state of the active tree, or of `treeGeneration`
+  async getTreeState(forceRefresh = false, treeGeneration?: number): Promise<OnChainTreeState> {
     const now = Date.now();
-    if (!forceRefresh && this.cachedTreeState && (now - this.cacheTimestamp) < this.CACHE_TTL) {
+    const cacheable = treeGeneration === undefined;
+    if (cacheable && !forceRefresh && this.cachedTreeState && (now - this.cacheTimestamp) < this.CACHE_TTL) {
       return this.cachedTreeState;
     }
 
     const connection = this.getConnection();
-    const [merkleTreePDA] = getMerkleTreePDA();
+    const [merkleTreePDA] = getMerkleTreePDA(treeGeneration ?? await fetchCurrentGeneration(connection));
 
     const accountInfo = await connection.getAccountInfo(merkleTreePDA);
     if (!accountInfo) {
@@ -201,31 +338,32 @@ class RelayerCore {
     // MerkleTree layout (zero_copy, #[repr(C)]):
     // 8 bytes: anchor discriminator
     // 32 bytes: authority (Pubkey)
+    // 8 bytes: generation (u64 LE)
     // 8 bytes: next_index (u64 LE)
     // 32 bytes: current_root
-    // 1024 bytes: root_history (32 roots x 32 bytes)
+    // 8192 bytes: root_history (256 roots x 32 bytes)
     // 8 bytes: root_history_index (u64 LE)
     // 1 byte: height
     // 1 byte: bump
-    // 6 bytes: padding
+    // 2 bytes: root_history_size (u16 LE)
+    // ... version, filled_subtrees, zeros, sequence, reserved
 
     let offset = 8; // Skip discriminator
 
     const authority = new PublicKey(data.slice(offset, offset + 32));
     offset += 32;
 
+    const generation = new BN(data.slice(offset, offset + 8), 'le').toNumber();
+    offset += 8;
+
     const nextIndex = new BN(data.slice(offset, offset + 8), 'le').toNumber();
     offset += 8;
 
     const currentRoot = new Uint8Array(data.slice(offset, offset + 32));
     offset += 32;
 
-    const rootHistoryRaw = data.slice(offset, offset + 1024);
-    const rootHistory: Uint8Array[] = [];
-    for (let i = 0; i < ROOT_HISTORY_SIZE; i++) {
-      rootHistory.push(new Uint8Array(rootHistoryRaw.slice(i * 32, (i + 1) * 32)));
-    }
-    offset += 1024;
+    const rootHistoryRaw = data.slice(offset, offset + ROOT_HISTORY_SIZE * 32);
+    offset += ROOT_HISTORY_SIZE * 32;
 
     const rootHistoryIndex = new BN(data.slice(offset, offset + 8), 'le').toNumber();
     offset += 8;
@@ -234,19 +372,32 @@ class RelayerCore {
     offset += 1;
 
     const bump = data[offset];
+    offset += 1;
+
+    const rootHistorySize = data.readUInt16LE(offset);
+
+    const rootHistory: Uint8Array[] = [];
+    for (let i = 0; i < rootHistorySize; i++) {
+      rootHistory.push(new Uint8Array(rootHistoryRaw.slice(i * 32, (i + 1) * 32)));
+    }
 
-    this.cachedTreeState = {
+    const state: OnChainTreeState = {
       authority,
+      generation,
       nextIndex,
       currentRoot,
       rootHistory,
       rootHistoryIndex,
       height,
       bump,
+      rootHistorySize,
     };
-    this.cacheTimestamp = now;
+    if (cacheable) {
+      this.cachedTreeState = state;
+      this.cacheTimestamp = now;
+    }
 
-    return this.cachedTreeState;
+    return state;
   }
 
   // Index CommitmentInserted events from on-chain transactions
@@ -268,101 +419,31 @@ class RelayerCore {
 
     try {
       const connection = this.getConnection();
-      const [merkleTreePDA] = getMerkleTreePDA();
+      this.currentGeneration = await fetchCurrentGeneration(connection);
 
       console.log('[RelayerCore] Indexing commitment events...');
 
-      const signatures = await connection.getSignaturesForAddress(
-        merkleTreePDA,
-        { limit: 1000 },
-        'confirmed'
-      );
-
-      if (signatures.length === 0) {
-        console.log('[RelayerCore] No transactions found');
-        return;
-      }
-
-      // Process oldest first
-      const sigsToProcess = [...signatures].reverse();
-      let newCommitmentsFound = 0;
+      // Notes stay spendable in every generation, so index them all. A rolled-over
+      // tree takes no more leaves and is skipped once fully indexed.
+      for (let generation = 0; generation <= this.currentGeneration; generation++) {
+        const index = this.getGenerationIndex(generation);
+        if (index.sealed) continue;
 
-      for (const sigInfo of sigsToProcess) {
-        if (sigInfo.err) continue;
+        const newCommitmentsFound = await this.indexGeneration(generation);
+        console.log(`[RelayerCore] Generation ${generation}: indexed ${newCommitmentsFound} new commitments`);
 
-        try {
-          const tx = await connection.getTransaction(sigInfo.signature, {
-            maxSupportedTransactionVersion: 0,
-            commitment: 'confirmed',
-          });
-
-          if (!tx?.meta?.logMessages) continue;
-
-          for (const log of tx.meta.logMessages) {
-            if (!log.startsWith('Program data: ')) continue;
-
-            try {
-              const eventData = Buffer.from(log.slice('Program data: '.length), 'base64');
-
-              // CommitmentInserted event layout:
-              // 8 bytes: discriminator (sha256("event:CommitmentInserted")[0..8])
-              // 8 bytes: index (u64 LE)
-              // 32 bytes: commitment
-              // 4 bytes: encrypted_output length (u32 LE)
-              // N bytes: encrypted_output data
-              // 8 bytes: amount
-              // 1 byte: Option tag for mint
-              // 32 bytes: mint (if Some)
-
-              if (eventData.length < 52) continue;
-
-              const index = new BN(eventData.slice(8, 16), 'le').toNumber();
-              const commitment = eventData.slice(16, 48);
-              const encLen = eventData.readUInt32LE(48);
-
-              if (encLen === 0 || encLen > 10000 || 52 + encLen > eventData.length) continue;
-
-              const encryptedOutput = eventData.slice(52, 52 + encLen).toString('hex');
-              const commitmentHex = Buffer.from(commitment).toString('hex');
-
-              if (this.utxoIndex.has(encryptedOutput)) continue;
-
-              // Convert commitment to decimal string (matches SDK format)
-              const commitmentDecimal = new BN(commitment).toString();
-
-              const entry: CommitmentEntry = {
-                index,
-                commitment: new Uint8Array(commitment),
-                commitmentHex,
-                commitmentDecimal,
-                encryptedOutput,
-              };
-
-              this.utxoIndex.set(encryptedOutput, entry);
-              this.commitmentIndex.set(commitmentHex, entry);
-              this.encryptedOutputsByIndex[index] = encryptedOutput;
-              this.commitmentsByIndex[index] = commitmentDecimal;
-              newCommitmentsFound++;
-            } catch {
-              // Not a valid event, skip
-            }
-          }
-        } catch {
-          // Skip individual transaction errors
+        // Rebuild Merkle tree if we have new commitments
+        if (newCommitmentsFound > 0 || !index.tree) {
+          await this.rebuildMerkleTree(generation);
         }
-      }
-
-      console.log(`[RelayerCore] Indexed ${newCommitmentsFound} new commitments. Total: ${this.utxoIndex.size}`);
-
-      // Rebuild Merkle tree if we have new commitments
-      if (newCommitmentsFound > 0 || !this.localTree) {
-        await this.rebuildMerkleTree();
-
-        // Update root on-chain if it has changed
-        if (newCommitmentsFound > 0) {
-          await this.updateRootOnChain();
+        if (generation < this.currentGeneration) {
+          index.sealed = await this.checkRootAgainstChain(generation);
+        } else if (newCommitmentsFound > 0) {
+          await this.checkRootAgainstChain(generation);
         }
       }
+
+      console.log(`[RelayerCore] Total indexed commitments: ${this.utxoIndex.size}`);
     } catch (e) {
       console.error('[RelayerCore] Error indexing commitments:', e);
     } finally {
@@ -371,50 +452,134 @@ class RelayerCore {
     }
   }
 
-  // Update the on-chain root if local tree root differs from on-chain
-  private async updateRootOnChain(): Promise<void> {
-    try {
-      if (!this.localTree) {
-        console.log('[RelayerCore] No local tree to update root from');
-        return;
-      }
+  private getGenerationIndex(generation: number): GenerationIndex {
+    let index = this.generations.get(generation);
+    if (!index) {
+      index = { commitmentsByIndex: [], encryptedOutputsByIndex: [], tree: null, sealed: false };
+      this.generations.set(generation, index);
+    }
+    return index;
+  }
 
-      // Get local tree root
-      const localRoot = this.localTree.layers[this.localTree.levels]?.[0] || this.localTree.zeros[this.localTree.levels];
+  // Index CommitmentInserted events of one tree generation; returns the number of new leaves
+  private async indexGeneration(generation: number): Promise<number> {
+    const connection = this.getConnection();
+    const index = this.getGenerationIndex(generation);
+    const [merkleTreePDA] = getMerkleTreePDA(generation);
 
-      // Get on-chain tree state
-      const treeState = await this.getTreeState(true); // force refresh
-      const onChainRootHex = Buffer.from(treeState.currentRoot).toString('hex');
+    const signatures = await connection.getSignaturesForAddress(
+      merkleTreePDA,
+      { limit: 1000 },
+      'confirmed'
+    );
 
-      // Convert local root to hex for comparison
-      const localRootBN = new BN(localRoot);
-      const localRootHex = localRootBN.toBuffer('be', 32).toString('hex');
+    // Process oldest first
+    const sigsToProcess = [...signatures].reverse();
+    let newCommitmentsFound = 0;
 
-      if (localRootHex === onChainRootHex) {
-        console.log('[RelayerCore] Root already up-to-date on-chain');
-        return;
+    for (const sigInfo of sigsToProcess) {
+      if (sigInfo.err) continue;
+
+      try {
+        const tx = await connection.getTransaction(sigInfo.signature, {
+          maxSupportedTransactionVersion: 0,
+          commitment: 'confirmed',
+        });
+
+        if (!tx?.meta?.logMessages) continue;
+
+        for (const log of tx.meta.logMessages) {
+          if (!log.startsWith('Program data: ')) continue;
+
+          try {
+            const eventData = Buffer.from(log.slice('Program data: '.length), 'base64');
+
+            // CommitmentInserted event layout:
+            // 8 bytes: discriminator (sha256("event:CommitmentInserted")[0..8])
+            // 32 bytes: header (pool_id, generation, sequence, slot; u64 LE each)
+            // 8 bytes: index (u64 LE)
+            // 32 bytes: commitment
+            // 4 bytes: encrypted_output length (u32 LE)
+            // N bytes: encrypted_output data
+            // 1 + 8 bytes: amount (Option<u64>)
+            // 1 + 32 bytes: mint (Option<Pubkey>)
+
+            if (eventData.length < 84) continue;
+            if (!eventData.subarray(0, 8).equals(COMMITMENT_INSERTED_DISCRIMINATOR)) continue;
+            // Only leaves of this pool's tree of this generation
+            if (eventData.readBigUInt64LE(8) !== ZK_POOL_ID) continue;
+            if (eventData.readBigUInt64LE(16) !== BigInt(generation)) continue;
+
+            const leafIndex = new BN(eventData.slice(40, 48), 'le').toNumber();
+            const commitment = eventData.slice(48, 80);
+            const encLen = eventData.readUInt32LE(80);
+
+            if (encLen === 0 || encLen > 10000 || 84 + encLen > eventData.length) continue;
+
+            const encryptedOutput = eventData.slice(84, 84 + encLen).toString('hex');
+            const commitmentHex = Buffer.from(commitment).toString('hex');
+
+            if (this.utxoIndex.has(encryptedOutput)) continue;
+
+            // Convert commitment to decimal string (matches SDK format)
+            const commitmentDecimal = new BN(commitment).toString();
+
+            const entry: CommitmentEntry = {
+              generation,
+              index: leafIndex,
+              commitment: new Uint8Array(commitment),
+              commitmentHex,
+              commitmentDecimal,
+              encryptedOutput,
+            };
+
+            this.utxoIndex.set(encryptedOutput, entry);
+            this.commitmentIndex.set(commitmentHex, entry);
+            index.encryptedOutputsByIndex[leafIndex] = encryptedOutput;
+            index.commitmentsByIndex[leafIndex] = commitmentDecimal;
+            newCommitmentsFound++;
+          } catch {
+            // Not a valid event, skip
+          }
+        }
+      } catch {
+        // Skip individual transaction errors
       }
+    }
+
+    return newCommitmentsFound;
+  }
 
-      console.log(`[RelayerCore] Updating root on-chain: ${localRoot.slice(0, 20)}...`);
+  // The program maintains the root itself on every insert; a mismatch means
+  // the local index is missing commitments and proofs built from it will fail
+  private async checkRootAgainstChain(generation: number): Promise<boolean> {
+    try {
+      if (!this.generations.get(generation)?.tree) {
+        return false;
+      }
 
-      // Import ZkPoolClient dynamically to avoid circular dependency
-      const { getZkPoolClient } = await import('./zk-pool-client');
-      const zkClient = await getZkPoolClient();
+      const localRoot = this.getMerkleRoot(generation);
+      const treeState = await this.getTreeState(true, generation);
+      const onChainRootHex = Buffer.from(treeState.currentRoot).toString('hex');
+      const localRootHex = new BN(localRoot).toBuffer('be', 32).toString('hex');
 
-      const result = await zkClient.updateRoot(localRoot);
-      if (result.success) {
-        console.log(`[RelayerCore] Root updated on-chain: ${result.signature}`);
-      } else {
-        console.error(`[RelayerCore] Failed to update root: ${result.error}`);
+      if (localRootHex !== onChainRootHex) {
+        console.warn(
+          `[RelayerCore] Generation ${generation}: local root ${localRootHex.slice(0, 16)}... differs from on-chain root ${onChainRootHex.slice(0, 16)}...; index is incomplete`
+        );
+        return false;
       }
+      return true;
     } catch (error) {
-      console.error('[RelayerCore] Error updating root on-chain:', error);
-      // Don't throw - indexing should continue even if root update fails
+      console.error('[RelayerCore] Error checking root against chain:', error);
+      // Don't throw - indexing should continue even if the check fails
+      return false;
     }
   }
 
-  // Rebuild the full Poseidon Merkle tree from indexed commitments
-  private async rebuildMerkleTree(): Promise<void> {
+  // Rebuild the full Poseidon Merkle tree of a generation from indexed commitments
+  private async rebuildMerkleTree(generation: number): Promise<void> {
+    const index = this.getGenerationIndex(generation);
     const wasm = await this.ensurePoseidon();
     const levels = MERKLE_TREE_HEIGHT;
 
@@ -427,13 +592,13 @@ class RelayerCore {
 
     // Collect all leaf commitments
     const leaves: string[] = [];
-    for (let i = 0; i < this.commitmentsByIndex.length; i++) {
-      leaves.push(this.commitmentsByIndex[i] || '0');
+    for (let i = 0; i < index.commitmentsByIndex.length; i++) {
+      leaves.push(index.commitmentsByIndex[i] || '0');
     }
 
     if (leaves.length === 0) {
-      this.localTree = { layers: [[]], zeros, levels };
-      console.log('[RelayerCore] Merkle tree rebuilt with 0 leaves');
+      index.tree = { layers: [[]], zeros, levels };
+      console.log(`[RelayerCore] Generation ${generation} Merkle tree rebuilt with 0 leaves`);
       return;
     }
 
@@ -451,10 +616,10 @@ class RelayerCore {
       }
     }
 
-    this.localTree = { layers, zeros, levels };
+    index.tree = { layers, zeros, levels };
 
     const computedRoot = layers[levels]?.length > 0 ? layers[levels][0] : zeros[levels];
-    console.log(`[RelayerCore] Merkle tree rebuilt with ${leaves.length} leaves, root: ${computedRoot.slice(0, 20)}...`);
+    console.log(`[RelayerCore] Generation ${generation} Merkle tree rebuilt with ${leaves.length} leaves, root: ${computedRoot.slice(0, 20)}...`);
   }
 
   // Find commitment entry by hex or decimal string
@@ -475,11 +640,12 @@ class RelayerCore {
     return entry;
   }
 
-  // Generate Merkle proof for a commitment
+  // Generate Merkle proof for a commitment against the tree of its generation
   async generateMerkleProof(commitmentParam: string): Promise<{
     pathElements: string[];
     pathIndices: number[];
     root: string;
+    generation: number;
   }> {
     let entry = this.findCommitmentEntry(commitmentParam);
 
@@ -494,16 +660,18 @@ class RelayerCore {
       throw new Error(`Commitment not found: ${commitmentParam}`);
     }
 
-    // Ensure tree is built
-    if (!this.localTree || this.localTree.layers[0].length === 0) {
-      await this.rebuildMerkleTree();
+    // Ensure the note's tree is built
+    const generation = entry.generation;
+    const index = this.getGenerationIndex(generation);
+    if (!index.tree || index.tree.layers[0].length === 0) {
+      await this.rebuildMerkleTree(generation);
     }
 
-    if (!this.localTree) {
-      throw new Error('Local Merkle tree is empty');
+    if (!index.tree) {
+      throw new Error(`Local Merkle tree of generation ${generation} is empty`);
     }
 
-    const tree = this.localTree;
+    const tree = index.tree;
     const targetIndex = entry.index;
 
     // Generate path (same logic as SDK's MerkleTree.path())
@@ -525,19 +693,20 @@ class RelayerCore {
       ? tree.layers[tree.levels][0]
       : tree.zeros[tree.levels];
 
-    console.log(`[RelayerCore] Generated proof for index ${targetIndex}, root: ${root.slice(0, 20)}...`);
+    console.log(`[RelayerCore] Generated proof for ${generation}:${targetIndex}, root: ${root.slice(0, 20)}...`);
 
-    return { pathElements, pathIndices, root };
+    return { pathElements, pathIndices, root, generation };
   }
 
-  // Get current Merkle root (from local tree)
-  getMerkleRoot(): string {
-    if (!this.localTree) {
+  // Get the Merkle root of a generation (default: the active one) from its local tree
+  getMerkleRoot(generation: number = this.currentGeneration): string {
+    const tree = this.generations.get(generation)?.tree;
+    if (!tree) {
       throw new Error('Merkle tree not built');
     }
-    return this.localTree.layers[this.localTree.levels]?.length > 0
-      ? this.localTree.layers[this.localTree.levels][0]
-      : this.localTree.zeros[this.localTree.levels];
+    return tree.layers[tree.levels]?.length > 0
+      ? tree.layers[tree.levels][0]
+      : tree.zeros[tree.levels];
   }
 
   // Get next leaf index from on-chain state
@@ -552,12 +721,13 @@ class RelayerCore {
     return wasm.poseidonHashString(inputs);
   }
 
-  // Generate commitment data (for deposit)
-  async generateCommitment(value: bigint): Promise<{
+  // Generate commitment data (for deposit); omit the mint for SOL notes
+  async generateCommitment(value: bigint, mint?: PublicKey): Promise<{
     commitment: string;
     nullifier: string;
     secret: string;
     precommitment: string;
+    assetId: string;
   }> {
     const wasm = await this.ensurePoseidon();
 
@@ -571,10 +741,11 @@ class RelayerCore {
     // precommitment = Poseidon(nullifier, secret)
     const precommitment = wasm.poseidonHashString([nullifier, secret]);
 
-    // commitment = Poseidon(value, precommitment)
-    const commitment = wasm.poseidonHashString([value.toString(), precommitment]);
+    // commitment = Poseidon(value, asset_id, precommitment), as derived on-chain
+    const assetId = await getAssetId(mint);
+    const commitment = wasm.poseidonHashString([value.toString(), assetId, precommitment]);
 
-    return { commitment, nullifier, secret, precommitment };
+    return { commitment, nullifier, secret, precommitment, assetId };
   }
 
   // Encrypt output (simple XOR with shared secret for now - can be upgraded to proper encryption)
@@ -594,20 +765,25 @@ class RelayerCore {
     return new TextEncoder().encode(json);
   }
 
-  // Get UTXOs by encrypted output range
+  // Get UTXOs by encrypted output range, over all generations in (generation, index) order
   getEncryptedOutputsRange(start: number, end: number): {
     encrypted_outputs: string[];
     hasMore: boolean;
     total: number;
   } {
+    const all: string[] = [];
+    for (let generation = 0; generation <= this.currentGeneration; generation++) {
+      all.push(...(this.generations.get(generation)?.encryptedOutputsByIndex ?? []));
+    }
+
     const outputs: string[] = [];
-    for (let i = start; i < Math.min(end, this.encryptedOutputsByIndex.length); i++) {
-      if (this.encryptedOutputsByIndex[i]) {
-        outputs.push(this.encryptedOutputsByIndex[i]);
+    for (let i = start; i < Math.min(end, all.length); i++) {
+      if (all[i]) {
+        outputs.push(all[i]);
       }
     }
 
-    const total = this.commitmentsByIndex.length;
+    const total = all.length;
     return {
       encrypted_outputs: outputs,
       hasMore: end < total,
@@ -7,9 +7,33 @@ export interface NoirProof {
     stateRoot: bigint;
     newCommitment: bigint;
     nullifierHash: bigint;
+    assetId: bigint;
+    extDataHash: bigint;
   };
 }
 
+// Inputs of the withdrawal circuit (circuits-noir/src/main.nr), as decimal field elements
+export interface WithdrawalWitness {
+  // Public, in verifier order
+  withdrawnValue: string;
+  stateRoot: string;
+  newCommitment: string;
+  nullifierHash: string;
+  assetId: string;
+  extDataHash: string;
+  // Private
+  existingValue: string;
+  existingNullifier: string;
+  existingSecret: string;
+  newNullifier: string;
+  newSecret: string;
+  pathElements: string[];
+  pathIndices: number[];
+}
+
+// Proves a withdrawal witness, returning the Groth16 proof bytes the pool's verifier expects
+export type WithdrawalProver = (witness: WithdrawalWitness) => Promise<Uint8Array>;
+
 export interface CommitmentData {
   commitment: bigint;
   nullifier: bigint;
@@ -33,6 +57,7 @@ export interface DepositParams {
 export interface WithdrawParams {
   lamports: number;
   recipientAddress: string;
+  prover?: WithdrawalProver;
   utxos?: Array<{
     commitment: string;
     nullifier: string;
@@ -51,6 +76,7 @@ export interface WithdrawSPLParams {
   amount: number;
   mintAddress: PublicKey;
   recipientAddress: string;
+  prover?: WithdrawalProver;
   utxos?: Array<{
     commitment: string;
     nullifier: string;
@@ -1,8 +1,9 @@
 /**
  * ZK Pool Client - Direct on-chain interaction with siphon-zk-pool program
  *
- * Uses Anchor to build and send transactions for deposits, withdrawals,
- * and root updates. No separate relayer server required.
+ * Uses Anchor to build and send transactions for deposits and withdrawals.
+ * The program updates the Merkle root itself on every insert.
+ * No separate relayer server required.
  */
 
 import {
@@ -72,8 +73,16 @@ import {
   getPoolVaultPDA,
   getNullifierPDA,
   getCommitmentRecordPDA,
+  getMintConfigPDA,
+  fetchCurrentGeneration,
+  fetchFeeConfig,
+  splitWithdrawalFee,
+  getExtDataHash,
+  POOL_CONFIG_FEE_RECIPIENT_OFFSET,
+  POOL_CONFIG_VERIFIER_PROGRAM_OFFSET,
+  WITHDRAW_INPUTS_VERSION,
 } from './relayer-core';
-import { TransactionResult } from './types';
+import type { WithdrawalProver } from './types';
 
 // Import the IDL
 import idl from '../siphon/zk-pool-idl.json';
@@ -160,11 +169,10 @@ export class ZkPoolClient {
       console.log(`[ZkPoolClient] Depositing ${lamports / LAMPORTS_PER_SOL} SOL, leaf index: ${nextIndex}`);
 
       // Generate commitment
-      const { commitment, nullifier, secret } = await relayer.generateCommitment(BigInt(lamports));
+      const { commitment, nullifier, secret, precommitment } = await relayer.generateCommitment(BigInt(lamports));
 
-      // Convert commitment to bytes
-      const commitmentBN = new BN(commitment);
-      const commitmentBytes = commitmentBN.toArray('be', 32);
+      // The program derives the commitment from the precommitment, amount and asset
+      const precommitmentBytes = new BN(precommitment).toArray('be', 32);
 
       // Create encrypted output (for client-side UTXO management)
       const encryptedOutput = relayer.encryptOutput({
@@ -175,15 +183,16 @@ export class ZkPoolClient {
       });
 
       // Derive PDAs
-      const [merkleTreePDA] = getMerkleTreePDA();
+      const generation = await fetchCurrentGeneration(this.connection);
+      const [merkleTreePDA] = getMerkleTreePDA(generation);
       const [poolConfigPDA] = getPoolConfigPDA();
       const [poolVaultPDA] = getPoolVaultPDA();
-      const [commitmentRecordPDA] = getCommitmentRecordPDA(nextIndex);
+      const [commitmentRecordPDA] = getCommitmentRecordPDA(nextIndex, generation);
 
       // Build transaction
       const tx = await this.program.methods
         .depositSol(
-          commitmentBytes,
+          precommitmentBytes,
           Buffer.from(encryptedOutput),
           new BN(lamports),
           new BN(nextIndex)
@@ -243,14 +252,118 @@ export class ZkPoolClient {
     }
   }
 
+  /**
+   * Build the WithdrawInputs and proof that spend `commitmentData`.
+   *
+   * Creates the change note (if any), binds the payout and the encrypted change
+   * note through ext_data_hash and proves the withdrawal circuit.
+   */
+  private async prepareWithdrawal(params: {
+    commitmentData: {
+      commitment: string;
+      nullifier: string;
+      secret: string;
+      value: string;
+    };
+    mint?: PublicKey;
+    recipient: PublicKey;
+    amount: bigint;
+    fee: bigint;
+    feeRecipient: PublicKey;
+    prover: WithdrawalProver;
+  }) {
+    const relayer = await getRelayerCore();
+    const { commitmentData, recipient, amount, fee, feeRecipient } = params;
+    const mint = params.mint ?? PublicKey.default;
+
+    // Generate Merkle proof against the tree holding the note (any generation)
+    const merkleProof = await relayer.generateMerkleProof(commitmentData.commitment);
+
+    // Compute nullifier hash = Poseidon(nullifier)
+    const nullifierHash = await relayer.poseidonHash([commitmentData.nullifier]);
+
+    // The note must cover amount + fee; whatever is left becomes the change note
+    const withdrawnValue = amount + fee;
+    const changeValue = BigInt(commitmentData.value) - withdrawnValue;
+    if (changeValue < 0n) {
+      throw new Error('Withdrawal exceeds note value');
+    }
+
+    // Fresh nullifier/secret for the change note; with no change the commitment is zero
+    const change = await relayer.generateCommitment(changeValue, params.mint);
+    let newCommitment = '0';
+    let changeLeafIndex: number | null = null;
+    let encryptedOutput = new Uint8Array(0);
+    if (changeValue > 0n) {
+      newCommitment = change.commitment;
+      changeLeafIndex = await relayer.getNextIndex();
+      encryptedOutput = relayer.encryptOutput({
+        value: changeValue,
+        nullifier: change.nullifier,
+        secret: change.secret,
+        leafIndex: changeLeafIndex,
+      });
+    }
+
+    const extDataHash = await getExtDataHash({
+      recipient,
+      mint,
+      amount,
+      fee,
+      feeRecipient,
+      encryptedOutput,
+    });
+
+    const proof = await params.prover({
+      withdrawnValue: withdrawnValue.toString(),
+      stateRoot: merkleProof.root,
+      newCommitment,
+      nullifierHash,
+      assetId: change.assetId,
+      extDataHash,
+      existingValue: commitmentData.value,
+      existingNullifier: commitmentData.nullifier,
+      existingSecret: commitmentData.secret,
+      newNullifier: change.nullifier,
+      newSecret: change.secret,
+      pathElements: merkleProof.pathElements,
+      pathIndices: merkleProof.pathIndices,
+    });
+
+    const nullifierHashBytes = new BN(nullifierHash).toArray('be', 32);
+    const inputs = {
+      version: WITHDRAW_INPUTS_VERSION,
+      nullifierHash: nullifierHashBytes,
+      stateRoot: new BN(merkleProof.root).toArray('be', 32),
+      rootGeneration: new BN(merkleProof.generation),
+      newCommitment: new BN(newCommitment).toArray('be', 32),
+      recipient,
+      mint,
+      amount: new BN(amount.toString()),
+      fee: new BN(fee.toString()),
+      feeRecipient,
+      encryptedOutput: Buffer.from(encryptedOutput),
+    };
+
+    return {
+      inputs,
+      proof: Buffer.from(proof),
+      nullifierHash,
+      nullifierHashBytes,
+      changeLeafIndex,
+      rootGeneration: merkleProof.generation,
+    };
+  }
+
   /**
    * Withdraw SOL from the ZK pool.
    *
    * Flow:
    * 1. Get Merkle proof for commitment
-   * 2. Compute nullifier hash
-   * 3. Build withdraw_sol instruction
-   * 4. Send transaction
+   * 2. Compute nullifier hash and the change note
+   * 3. Prove the withdrawal circuit
+   * 4. Build withdraw_sol instruction
+   * 5. Send transaction
    */
   async withdrawSOL(
     recipientAddress: string,
@@ -260,7 +373,8 @@ export class ZkPoolClient {
       nullifier: string;
       secret: string;
       value: string;
-    }
+    },
+    prover: WithdrawalProver
   ): Promise<WithdrawResult> {
     try {
       const relayer = await getRelayerCore();
@@ -268,81 +382,62 @@ export class ZkPoolClient {
 
       console.log(`[ZkPoolClient] Withdrawing ${lamports / LAMPORTS_PER_SOL} SOL to ${recipientAddress}`);
 
-      // Generate Merkle proof
-      const proof = await relayer.generateMerkleProof(commitmentData.commitment);
-
-      // Compute nullifier hash = Poseidon(nullifier)
-      const nullifierHash = await relayer.poseidonHash([commitmentData.nullifier]);
-      const nullifierHashBN = new BN(nullifierHash);
-      const nullifierHashBytes = nullifierHashBN.toArray('be', 32);
-
-      // Get state root (from proof)
-      const stateRootBN = new BN(proof.root);
-      const stateRootBytes = stateRootBN.toArray('be', 32);
-
-      // For partial withdrawals, compute new commitment
-      // For full withdrawals, new commitment is zero
-      const originalValue = BigInt(commitmentData.value);
-      const withdrawValue = BigInt(lamports);
-      const changeValue = originalValue - withdrawValue;
-
-      let newCommitmentBytes: number[];
-      if (changeValue > 0n) {
-        // Partial withdrawal - generate change commitment
-        const changeCommitment = await relayer.generateCommitment(changeValue);
-        const changeCommitmentBN = new BN(changeCommitment.commitment);
-        newCommitmentBytes = changeCommitmentBN.toArray('be', 32);
-      } else {
-        // Full withdrawal - no change
-        newCommitmentBytes = new Array(32).fill(0);
-      }
-
-      // Calculate fee (25 bps = 0.25%)
-      const fee = Math.floor(lamports * 25 / 10000);
-      const amountAfterFee = lamports - fee;
+      // Fee is capped on-chain by PoolConfig.fee_bps of the gross amount
+      const { amount: amountAfterFee, fee } = splitWithdrawalFee(
+        BigInt(lamports),
+        await fetchFeeConfig(this.connection)
+      );
 
       // Derive PDAs
-      const [merkleTreePDA] = getMerkleTreePDA();
+      const generation = await fetchCurrentGeneration(this.connection);
+      const [merkleTreePDA] = getMerkleTreePDA(generation);
       const [poolConfigPDA] = getPoolConfigPDA();
       const [poolVaultPDA] = getPoolVaultPDA();
-      const [nullifierPDA] = getNullifierPDA(new Uint8Array(nullifierHashBytes));
 
-      // Fetch PoolConfig to get the fee_recipient
+      // Fetch PoolConfig to get the fee_recipient and withdrawal verifier
       const poolConfigAccount = await this.connection.getAccountInfo(poolConfigPDA);
       if (!poolConfigAccount) {
         throw new Error('PoolConfig account not found');
       }
 
-      // PoolConfig layout (Borsh): 8 (disc) + 32 (admin) + 32 (relayer) + 2 (fee_bps) + 32 (fee_recipient) + ...
-      const feeRecipientOffset = 8 + 32 + 32 + 2;
+      const feeRecipientOffset = POOL_CONFIG_FEE_RECIPIENT_OFFSET;
       const feeRecipientBytes = poolConfigAccount.data.slice(feeRecipientOffset, feeRecipientOffset + 32);
       const feeRecipient = new PublicKey(feeRecipientBytes);
-
-      console.log(`[ZkPoolClient] Using fee_recipient from PoolConfig: ${feeRecipient.toBase58()}`);
-
-      // Build WithdrawInputs struct
-      const withdrawInputs = {
-        nullifierHash: nullifierHashBytes,
-        stateRoot: stateRootBytes,
-        newCommitment: newCommitmentBytes,
-      };
+      const verifierOffset = POOL_CONFIG_VERIFIER_PROGRAM_OFFSET;
+      const verifierProgram = new PublicKey(poolConfigAccount.data.slice(verifierOffset, verifierOffset + 32));
+
+      const {
+        inputs, proof, nullifierHash, nullifierHashBytes, changeLeafIndex, rootGeneration,
+      } = await this.prepareWithdrawal({
+        commitmentData,
+        recipient,
+        amount: amountAfterFee,
+        fee,
+        feeRecipient,
+        prover,
+      });
+      const [nullifierPDA] = getNullifierPDA(new Uint8Array(nullifierHashBytes));
+      const commitmentRecordPDA = changeLeafIndex === null
+        ? null
+        : getCommitmentRecordPDA(changeLeafIndex, generation)[0];
 
       // Build transaction
       const tx = await this.program.methods
-        .withdrawSol(
-          withdrawInputs,
-          recipient,
-          new BN(amountAfterFee),
-          new BN(fee)
-        )
+        .withdrawSol(inputs, proof)
         .accounts({
           relayer: this.executorKeypair.publicKey,
           poolConfig: poolConfigPDA,
+          rootTree: getMerkleTreePDA(rootGeneration)[0],
           merkleTree: merkleTreePDA,
           poolVault: poolVaultPDA,
           nullifierAccount: nullifierPDA,
+          nullifierShard: null,
+          commitmentRecord: commitmentRecordPDA,
           recipient,
+          recipientWsolAccount: null,
           feeRecipient,
+          verifierProgram,
+          tokenProgram: null,
           systemProgram: SystemProgram.programId,
         })
         .transaction();
@@ -388,50 +483,6 @@ export class ZkPoolClient {
     }
   }
 
-  /**
-   * Push a new Merkle root on-chain (relayer only).
-   */
-  async updateRoot(newRoot: string): Promise<TransactionResult> {
-    try {
-      const newRootBN = new BN(newRoot);
-      const newRootBytes = newRootBN.toArray('be', 32);
-
-      const [merkleTreePDA] = getMerkleTreePDA();
-      const [poolConfigPDA] = getPoolConfigPDA();
-
-      const tx = await this.program.methods
-        .updateRoot(newRootBytes)
-        .accounts({
-          relayer: this.executorKeypair.publicKey,
-          poolConfig: poolConfigPDA,
-          merkleTree: merkleTreePDA,
-        })
-        .transaction();
-
-      const { blockhash, lastValidBlockHeight } = await this.connection.getLatestBlockhash('confirmed');
-      tx.recentBlockhash = blockhash;
-      tx.feePayer = this.executorKeypair.publicKey;
-      tx.sign(this.executorKeypair);
-
-      const signature = await this.connection.sendRawTransaction(tx.serialize(), {
-        skipPreflight: false,
-        preflightCommitment: 'confirmed',
-      });
-
-      await confirmTransactionPolling(this.connection, signature, blockhash, lastValidBlockHeight, 'confirmed');
-
-      console.log(`[ZkPoolClient] Root updated: ${signature}`);
-
-      return { success: true, signature };
-    } catch (error) {
-      console.error('[ZkPoolClient] Update root error:', error);
-      return {
-        success: false,
-        error: error instanceof Error ? error.message : 'Root update failed',
-      };
-    }
-  }
-
   /**
    * Deposit USDC into the ZK pool.
    */
@@ -445,10 +496,9 @@ export class ZkPoolClient {
       console.log(`[ZkPoolClient] Depositing ${amount} USDC, leaf index: ${nextIndex}`);
 
       // Generate commitment
-      const { commitment, nullifier, secret } = await relayer.generateCommitment(BigInt(amount));
+      const { commitment, nullifier, secret, precommitment } = await relayer.generateCommitment(BigInt(amount), usdcMint);
 
-      const commitmentBN = new BN(commitment);
-      const commitmentBytes = commitmentBN.toArray('be', 32);
+      const precommitmentBytes = new BN(precommitment).toArray('be', 32);
 
       const encryptedOutput = relayer.encryptOutput({
         value: BigInt(amount),
@@ -458,10 +508,12 @@ export class ZkPoolClient {
       });
 
       // Derive PDAs
-      const [merkleTreePDA] = getMerkleTreePDA();
+      const generation = await fetchCurrentGeneration(this.connection);
+      const [merkleTreePDA] = getMerkleTreePDA(generation);
       const [poolConfigPDA] = getPoolConfigPDA();
       const [poolVaultPDA] = getPoolVaultPDA();
-      const [commitmentRecordPDA] = getCommitmentRecordPDA(nextIndex);
+      const [commitmentRecordPDA] = getCommitmentRecordPDA(nextIndex, generation);
+      const [mintConfigPDA] = getMintConfigPDA(usdcMint);
 
       // Pool token account is an ATA owned by poolVault
       const poolTokenAccount = await getAssociatedTokenAddress(
@@ -479,7 +531,7 @@ export class ZkPoolClient {
       // Build transaction
       const tx = await this.program.methods
         .depositSpl(
-          commitmentBytes,
+          precommitmentBytes,
           Buffer.from(encryptedOutput),
           new BN(amount),
           new BN(nextIndex)
@@ -489,6 +541,7 @@ export class ZkPoolClient {
           poolConfig: poolConfigPDA,
           merkleTree: merkleTreePDA,
           tokenMint: usdcMint,
+          mintConfig: mintConfigPDA,
           depositorTokenAccount,
           poolTokenAccount: poolTokenAccount,
           poolVault: poolVaultPDA,
@@ -549,7 +602,8 @@ export class ZkPoolClient {
       nullifier: string;
       secret: string;
       value: string;
-    }
+    },
+    prover: WithdrawalProver
   ): Promise<WithdrawResult> {
     try {
       const { DEVNET_TOKEN_MINTS } = await import('./types');
@@ -560,41 +614,18 @@ export class ZkPoolClient {
 
       console.log(`[ZkPoolClient] Withdrawing ${amount} USDC to ${recipientAddress}`);
 
-      // Generate Merkle proof
-      const proof = await relayer.generateMerkleProof(commitmentData.commitment);
-
-      // Compute nullifier hash
-      const nullifierHash = await relayer.poseidonHash([commitmentData.nullifier]);
-      const nullifierHashBN = new BN(nullifierHash);
-      const nullifierHashBytes = nullifierHashBN.toArray('be', 32);
-
-      // Get state root
-      const stateRootBN = new BN(proof.root);
-      const stateRootBytes = stateRootBN.toArray('be', 32);
-
-      // Handle change commitment
-      const originalValue = BigInt(commitmentData.value);
-      const withdrawValue = BigInt(amount);
-      const changeValue = originalValue - withdrawValue;
-
-      let newCommitmentBytes: number[];
-      if (changeValue > 0n) {
-        const changeCommitment = await relayer.generateCommitment(changeValue);
-        const changeCommitmentBN = new BN(changeCommitment.commitment);
-        newCommitmentBytes = changeCommitmentBN.toArray('be', 32);
-      } else {
-        newCommitmentBytes = new Array(32).fill(0);
-      }
-
-      // Calculate fee (25 bps = 0.25%)
-      const fee = Math.floor(amount * 25 / 10000);
-      const amountAfterFee = amount - fee;
+      // Fee is capped on-chain by the mint's fee_bps/min_fee of the gross amount
+      const { amount: amountAfterFee, fee } = splitWithdrawalFee(
+        BigInt(amount),
+        await fetchFeeConfig(this.connection, usdcMint)
+      );
 
       // Derive PDAs
-      const [merkleTreePDA] = getMerkleTreePDA();
+      const generation = await fetchCurrentGeneration(this.connection);
+      const [merkleTreePDA] = getMerkleTreePDA(generation);
       const [poolConfigPDA] = getPoolConfigPDA();
       const [poolVaultPDA] = getPoolVaultPDA();
-      const [nullifierPDA] = getNullifierPDA(new Uint8Array(nullifierHashBytes));
+      const [mintConfigPDA] = getMintConfigPDA(usdcMint);
 
       // Pool token account is an ATA owned by poolVault (same as deposit)
       const poolTokenAccount = await getAssociatedTokenAddress(
@@ -606,24 +637,17 @@ export class ZkPoolClient {
       // Get recipient's token account
       const recipientTokenAccount = await getAssociatedTokenAddress(usdcMint, recipient);
 
-      // Check if recipient's token account exists
-      const recipientAccountInfo = await this.connection.getAccountInfo(recipientTokenAccount);
-      const recipientTokenAccountExists = recipientAccountInfo !== null;
-      if (recipientTokenAccountExists) {
-        console.log('[ZkPoolClient] Recipient token account exists');
-      } else {
-        console.log('[ZkPoolClient] Recipient token account does not exist, will create');
-      }
-
-      // Fetch fee_recipient from PoolConfig
+      // Fetch fee_recipient and withdrawal verifier from PoolConfig
       const poolConfigAccount = await this.connection.getAccountInfo(poolConfigPDA);
       if (!poolConfigAccount) {
         throw new Error('PoolConfig account not found');
       }
 
-      const feeRecipientOffset = 8 + 32 + 32 + 2;
+      const feeRecipientOffset = POOL_CONFIG_FEE_RECIPIENT_OFFSET;
       const feeRecipientBytes = poolConfigAccount.data.slice(feeRecipientOffset, feeRecipientOffset + 32);
       const feeRecipient = new PublicKey(feeRecipientBytes);
+      const verifierOffset = POOL_CONFIG_VERIFIER_PROGRAM_OFFSET;
+      const verifierProgram = new PublicKey(poolConfigAccount.data.slice(verifierOffset, verifierOffset + 32));
 
       // Get fee recipient's token account
       const feeRecipientTokenAccount = await getAssociatedTokenAddress(usdcMint, feeRecipient);
@@ -637,32 +661,44 @@ export class ZkPoolClient {
         console.log('[ZkPoolClient] Fee recipient token account does not exist, will create');
       }
 
-      // Build WithdrawInputs
-      const withdrawInputs = {
-        nullifierHash: nullifierHashBytes,
-        stateRoot: stateRootBytes,
-        newCommitment: newCommitmentBytes,
-      };
+      const {
+        inputs, proof, nullifierHash, nullifierHashBytes, changeLeafIndex, rootGeneration,
+      } = await this.prepareWithdrawal({
+        commitmentData,
+        mint: usdcMint,
+        recipient,
+        amount: amountAfterFee,
+        fee,
+        feeRecipient,
+        prover,
+      });
+      const [nullifierPDA] = getNullifierPDA(new Uint8Array(nullifierHashBytes));
+      const commitmentRecordPDA = changeLeafIndex === null
+        ? null
+        : getCommitmentRecordPDA(changeLeafIndex, generation)[0];
 
-      // Build withdrawal instruction (not transaction yet)
+      // Build withdrawal instruction (not transaction yet); the program creates the
+      // recipient's token account if it does not exist
       const withdrawIx = await this.program.methods
-        .withdrawSpl(
-          withdrawInputs,
-          recipient,
-          new BN(amountAfterFee),
-          new BN(fee)
-        )
+        .withdrawSpl(inputs, proof)
         .accounts({
           relayer: this.executorKeypair.publicKey,
           poolConfig: poolConfigPDA,
+          rootTree: getMerkleTreePDA(rootGeneration)[0],
           merkleTree: merkleTreePDA,
           poolVault: poolVaultPDA,
           tokenMint: usdcMint,
+          mintConfig: mintConfigPDA,
           poolTokenAccount: poolTokenAccount,
+          recipient,
           recipientTokenAccount,
           feeRecipientTokenAccount,
           nullifierAccount: nullifierPDA,
+          nullifierShard: null,
+          commitmentRecord: commitmentRecordPDA,
+          verifierProgram,
           tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
         })
         .instruction();
@@ -673,19 +709,7 @@ export class ZkPoolClient {
       // 1. Compute budget
       tx.add(ComputeBudgetProgram.setComputeUnitLimit({ units: 500_000 }));
 
-      // 2. Create recipient token account if it doesn't exist (BEFORE withdrawal)
-      if (!recipientTokenAccountExists) {
-        console.log('[ZkPoolClient] Adding instruction to create recipient token account');
-        const createRecipientTokenAccountIx = createAssociatedTokenAccountInstruction(
-          this.executorKeypair.publicKey, // payer (executor pays for account creation)
-          recipientTokenAccount, // ata
-          recipient, // owner (recipient owns their token account)
-          usdcMint // mint
-        );
-        tx.add(createRecipientTokenAccountIx);
-      }
-
-      // 3. Create fee recipient token account if it doesn't exist (BEFORE withdrawal)
+      // 2. Create fee recipient token account if it doesn't exist (BEFORE withdrawal)
       if (!feeRecipientTokenAccountExists) {
         console.log('[ZkPoolClient] Adding instruction to create fee recipient token account');
         const createFeeRecipientTokenAccountIx = createAssociatedTokenAccountInstruction(
@@ -697,7 +721,7 @@ export class ZkPoolClient {
         tx.add(createFeeRecipientTokenAccountIx);
       }
 
-      // 4. Add withdrawal instruction (AFTER creating accounts)
+      // 3. Add withdrawal instruction (AFTER creating accounts)
       tx.add(withdrawIx);
 
       const { blockhash, lastValidBlockHeight } = await this.connection.getLatestBlockhash('confirmed');